@@ -1,80 +1,374 @@
 use winit::{
     application::ApplicationHandler,
-    event::{ElementState, MouseButton, WindowEvent},
+    dpi::PhysicalSize,
+    event::{DeviceEvent, DeviceId, ElementState, MouseButton, MouseScrollDelta, WindowEvent},
     event_loop::{self, EventLoop},
-    window::Window,
+    window::{CursorGrabMode, Fullscreen, Window},
 };
 
 use crate::{
     core::{
         GameEngine,
-        ecs::types::{CursorMovedEvent, KeyboardInputEvent},
+        config::EngineConfig,
+        ecs::types::{
+            CursorMovedEvent, KeyboardInputEvent, MouseButtonEvent, MouseMotionEvent,
+            MouseWheelEvent,
+        },
+        input::{CursorMode, InputRecording},
     },
     log,
 };
 
+// Touchpads report scrolling in pixels, convert it to roughly mouse wheel lines
+const PIXELS_PER_SCROLL_LINE: f64 = 20.0;
+
 type BenzeneAppCallback<S> = dyn FnMut(&mut GameEngine, &mut S);
+type BenzeneAppResizeCallback<S> = dyn FnMut(&mut GameEngine, &mut S, u32, u32);
+type BenzeneAppFocusCallback<S> = dyn FnMut(&mut GameEngine, &mut S, bool);
 
 pub struct BenzeneApp<S> {
+    config: EngineConfig,
     window: Option<Window>,
     engine: Option<GameEngine>,
     on_init: Box<BenzeneAppCallback<S>>,
     on_new_frame: Box<BenzeneAppCallback<S>>,
+    on_fixed_update: Option<Box<BenzeneAppCallback<S>>>,
+    on_resize: Option<Box<BenzeneAppResizeCallback<S>>>,
+    on_focus_changed: Option<Box<BenzeneAppFocusCallback<S>>>,
+    on_suspend: Option<Box<BenzeneAppCallback<S>>>,
+    on_resume: Option<Box<BenzeneAppCallback<S>>>,
+    on_shutdown: Option<Box<BenzeneAppCallback<S>>>,
 
     // State
     state: S,
-    cursor_locked: bool,
+    drag_to_look: bool,
+    applied_cursor_mode: CursorMode,
+    focused: bool,
+    minimized: bool,
 }
 
 impl<S> BenzeneApp<S> {
-    pub fn new(
-        state: S,
-        on_init: Box<BenzeneAppCallback<S>>,
-        on_new_frame: Box<BenzeneAppCallback<S>>,
+    pub fn builder(state: S) -> BenzeneAppBuilder<S> {
+        BenzeneAppBuilder::new(state)
+    }
+
+    // Blocks until the window is closed
+    pub fn run(mut self) {
+        let event_loop = EventLoop::new().expect("failed to create event loop");
+        event_loop
+            .run_app(&mut self)
+            .expect("failed to run event loop");
+    }
+
+    fn get_window_attributes(&self) -> winit::window::WindowAttributes {
+        let mut attributes = Window::default_attributes()
+            .with_title(self.config.title.clone())
+            .with_inner_size(PhysicalSize::new(self.config.width, self.config.height));
+        if self.config.fullscreen {
+            attributes = attributes.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        attributes
+    }
+
+    // Runs the shutdown hook, then tears the engine down before the window
+    // it renders to is dropped
+    fn shutdown(&mut self) {
+        if let Some(mut engine) = self.engine.take() {
+            log!("Shutting down");
+            if let Some(on_shutdown) = self.on_shutdown.as_mut() {
+                on_shutdown(&mut engine, &mut self.state);
+            }
+            if let (Some(recording), Some(path)) = (
+                engine.stop_input_recording(),
+                self.config.record_input_path.as_ref(),
+            ) {
+                recording.save_to_file(path);
+                log!(format!("Input recording saved to {}", path));
+            }
+            // Waits for the device to go idle before destroying anything
+            engine.destroy();
+        }
+        self.window = None;
+    }
+}
+
+pub struct BenzeneAppBuilder<S> {
+    config: EngineConfig,
+    state: S,
+    on_init: Option<Box<BenzeneAppCallback<S>>>,
+    on_new_frame: Option<Box<BenzeneAppCallback<S>>>,
+    on_fixed_update: Option<Box<BenzeneAppCallback<S>>>,
+    on_resize: Option<Box<BenzeneAppResizeCallback<S>>>,
+    on_focus_changed: Option<Box<BenzeneAppFocusCallback<S>>>,
+    on_suspend: Option<Box<BenzeneAppCallback<S>>>,
+    on_resume: Option<Box<BenzeneAppCallback<S>>>,
+    on_shutdown: Option<Box<BenzeneAppCallback<S>>>,
+    drag_to_look: bool,
+}
+
+impl<S> BenzeneAppBuilder<S> {
+    pub fn new(state: S) -> Self {
+        Self {
+            config: EngineConfig::default(),
+            state,
+            on_init: None,
+            on_new_frame: None,
+            on_fixed_update: None,
+            on_resize: None,
+            on_focus_changed: None,
+            on_suspend: None,
+            on_resume: None,
+            on_shutdown: None,
+            drag_to_look: true,
+        }
+    }
+
+    pub fn config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.config.title = title.into();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.config.width = width;
+        self.config.height = height;
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.config.fullscreen = fullscreen;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.config.vsync = vsync;
+        self
+    }
+
+    pub fn validation(mut self, enable_validation: bool) -> Self {
+        self.config.enable_validation = enable_validation;
+        self
+    }
+
+    pub fn preferred_gpu(mut self, name: &str) -> Self {
+        self.config.preferred_gpu = Some(name.into());
+        self
+    }
+
+    // Grab the cursor for mouse-look while the left button is held. Turn off
+    // to control the cursor with `GameEngine::set_cursor_mode` instead.
+    pub fn drag_to_look(mut self, drag_to_look: bool) -> Self {
+        self.drag_to_look = drag_to_look;
+        self
+    }
+
+    // Rate of `on_fixed_update` calls per second
+    pub fn fixed_update_rate(mut self, rate_hz: f32) -> Self {
+        self.config.fixed_update_rate = Some(rate_hz);
+        self
+    }
+
+    pub fn max_fixed_steps_per_frame(mut self, max_steps: u32) -> Self {
+        self.config.max_fixed_steps_per_frame = max_steps;
+        self
+    }
+
+    // Records all input and frame times, written to `path` on shutdown
+    pub fn record_input(mut self, path: &str) -> Self {
+        self.config.record_input_path = Some(path.into());
+        self
+    }
+
+    // Replays a recording made with `record_input` instead of live input
+    pub fn replay_input(mut self, path: &str) -> Self {
+        self.config.replay_input_path = Some(path.into());
+        self
+    }
+
+    pub fn on_init(mut self, on_init: impl FnMut(&mut GameEngine, &mut S) + 'static) -> Self {
+        self.on_init = Some(Box::new(on_init));
+        self
+    }
+
+    pub fn on_new_frame(
+        mut self,
+        on_new_frame: impl FnMut(&mut GameEngine, &mut S) + 'static,
+    ) -> Self {
+        self.on_new_frame = Some(Box::new(on_new_frame));
+        self
+    }
+
+    // Runs at the fixed update rate, possibly several times in one frame.
+    // Does nothing unless `fixed_update_rate` is set.
+    pub fn on_fixed_update(
+        mut self,
+        on_fixed_update: impl FnMut(&mut GameEngine, &mut S) + 'static,
+    ) -> Self {
+        self.on_fixed_update = Some(Box::new(on_fixed_update));
+        self
+    }
+
+    pub fn on_resize(
+        mut self,
+        on_resize: impl FnMut(&mut GameEngine, &mut S, u32, u32) + 'static,
+    ) -> Self {
+        self.on_resize = Some(Box::new(on_resize));
+        self
+    }
+
+    pub fn on_focus_changed(
+        mut self,
+        on_focus_changed: impl FnMut(&mut GameEngine, &mut S, bool) + 'static,
     ) -> Self {
-        let mut app = Self {
+        self.on_focus_changed = Some(Box::new(on_focus_changed));
+        self
+    }
+
+    // Called before the surface is destroyed
+    pub fn on_suspend(mut self, on_suspend: impl FnMut(&mut GameEngine, &mut S) + 'static) -> Self {
+        self.on_suspend = Some(Box::new(on_suspend));
+        self
+    }
+
+    // Called once the surface has been recreated
+    pub fn on_resume(mut self, on_resume: impl FnMut(&mut GameEngine, &mut S) + 'static) -> Self {
+        self.on_resume = Some(Box::new(on_resume));
+        self
+    }
+
+    // Called before the engine is destroyed
+    pub fn on_shutdown(
+        mut self,
+        on_shutdown: impl FnMut(&mut GameEngine, &mut S) + 'static,
+    ) -> Self {
+        self.on_shutdown = Some(Box::new(on_shutdown));
+        self
+    }
+
+    pub fn build(self) -> BenzeneApp<S> {
+        BenzeneApp {
+            config: self.config,
             window: None,
             engine: None,
-            on_init,
-            on_new_frame,
-            state,
-            cursor_locked: false,
-        };
-        let event_loop = EventLoop::new().expect("failed to create event loop");
-        let _ = event_loop.run_app(&mut app);
-
-        app
+            on_init: self.on_init.unwrap_or_else(|| Box::new(|_, _| {})),
+            on_new_frame: self.on_new_frame.unwrap_or_else(|| Box::new(|_, _| {})),
+            on_fixed_update: self.on_fixed_update,
+            on_resize: self.on_resize,
+            on_focus_changed: self.on_focus_changed,
+            on_suspend: self.on_suspend,
+            on_resume: self.on_resume,
+            on_shutdown: self.on_shutdown,
+            state: self.state,
+            drag_to_look: self.drag_to_look,
+            applied_cursor_mode: CursorMode::Normal,
+            focused: true,
+            minimized: false,
+        }
     }
 
-    pub fn run() {}
+    pub fn run(self) {
+        self.build().run();
+    }
 }
 
 impl<S> ApplicationHandler for BenzeneApp<S> {
     fn resumed(&mut self, event_loop: &event_loop::ActiveEventLoop) {
+        // Resuming after a suspend, the window and engine are still alive
+        if let (Some(engine), Some(window)) = (self.engine.as_mut(), self.window.as_ref()) {
+            log!("Resumed");
+            engine.resume(window);
+            if let Some(on_resume) = self.on_resume.as_mut() {
+                on_resume(engine, &mut self.state);
+            }
+            window.request_redraw();
+            return;
+        }
+
         let window = event_loop
-            .create_window(Window::default_attributes())
+            .create_window(self.get_window_attributes())
             .expect("failed to create window");
 
-        let mut engine = GameEngine::new(&window);
+        let mut engine = GameEngine::new(&window, &self.config);
 
         (self.on_init)(&mut engine, &mut self.state);
 
+        if let Some(path) = self.config.replay_input_path.as_ref() {
+            engine.start_input_replay(InputRecording::from_file(path));
+        }
+        if self.config.record_input_path.is_some() {
+            engine.start_input_recording();
+        }
+
         self.engine = Some(engine);
         self.window = Some(window);
     }
 
+    fn suspended(&mut self, _: &event_loop::ActiveEventLoop) {
+        if let Some(engine) = self.engine.as_mut() {
+            log!("Suspended");
+            if let Some(on_suspend) = self.on_suspend.as_mut() {
+                on_suspend(engine, &mut self.state);
+            }
+            engine.suspend();
+        }
+    }
+
+    fn device_event(&mut self, _: &event_loop::ActiveEventLoop, _: DeviceId, event: DeviceEvent) {
+        let Some(engine) = self.engine.as_mut() else {
+            return;
+        };
+        // Device events are delivered even when another window has focus
+        if !self.focused {
+            return;
+        }
+
+        if let DeviceEvent::MouseMotion { delta } = event {
+            engine.handle_mouse_motion(&MouseMotionEvent::new(delta.0, delta.1));
+        }
+    }
+
+    fn exiting(&mut self, _: &event_loop::ActiveEventLoop) {
+        self.shutdown();
+    }
+
     fn window_event(
         &mut self,
         event_loop: &event_loop::ActiveEventLoop,
         _: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
-        let engine = self.engine.as_mut().expect("game engine not initialized");
-        let window = self.window.as_ref().expect("window not initialized");
+        // Events can still arrive after shutdown
+        let (Some(engine), Some(window)) = (self.engine.as_mut(), self.window.as_ref()) else {
+            return;
+        };
 
         match event {
-            WindowEvent::Resized(_) => {
-                log!("WindowEvent: Resized");
+            WindowEvent::Resized(size) => {
+                log!(format!(
+                    "WindowEvent: Resized - {}x{}",
+                    size.width, size.height
+                ));
+
+                // Minimized, pause rendering until the window has an extent again
+                if size.width == 0 || size.height == 0 {
+                    self.minimized = true;
+                    return;
+                }
+
+                engine.emit_update_framebuffers(window);
+                if let Some(on_resize) = self.on_resize.as_mut() {
+                    on_resize(engine, &mut self.state, size.width, size.height);
+                }
+
+                if self.minimized {
+                    self.minimized = false;
+                    engine.reset_frame_clock();
+                    window.request_redraw();
+                }
             }
             WindowEvent::KeyboardInput { event, .. } => {
                 let ki_event =
@@ -84,34 +378,105 @@ impl<S> ApplicationHandler for BenzeneApp<S> {
                 engine.handle_keyboard_input(&ki_event);
             }
             WindowEvent::MouseInput { state, button, .. } => {
-                if let (ElementState::Pressed, MouseButton::Left) = (state, button) {
-                    self.cursor_locked = true;
-                }
-                if let (ElementState::Released, MouseButton::Left) = (state, button) {
-                    self.cursor_locked = false;
+                if self.drag_to_look && button == MouseButton::Left {
+                    engine.set_cursor_mode(match state {
+                        ElementState::Pressed => CursorMode::Grabbed,
+                        ElementState::Released => CursorMode::Normal,
+                    });
                 }
+
+                engine.handle_mouse_input(&MouseButtonEvent::new(button, state));
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let mw_event = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => MouseWheelEvent::new(x, y),
+                    MouseScrollDelta::PixelDelta(position) => MouseWheelEvent::new(
+                        (position.x / PIXELS_PER_SCROLL_LINE) as f32,
+                        (position.y / PIXELS_PER_SCROLL_LINE) as f32,
+                    ),
+                };
+                log!(format!("WindowEvent: MouseWheel - {:?}", mw_event));
+
+                engine.handle_mouse_wheel(&mw_event);
             }
             WindowEvent::CursorMoved { position, .. } => {
                 let cm_event = CursorMovedEvent::new(position.x, position.y);
                 log!(format!("WindowEvent: CursorMoved - {:?}", cm_event));
 
-                if self.cursor_locked {
-                    engine.handle_cursor_moved(&cm_event);
+                engine.handle_cursor_moved(&cm_event);
+            }
+            WindowEvent::Focused(focused) => {
+                log!(format!("WindowEvent: Focused - {}", focused));
+
+                self.focused = focused;
+                if !focused {
+                    engine.release_all_input();
+                    if self.drag_to_look {
+                        engine.set_cursor_mode(CursorMode::Normal);
+                    }
+                    // The OS drops the grab, so apply the mode again once focused
+                    window.set_cursor_visible(true);
+                    self.applied_cursor_mode = CursorMode::Normal;
+                }
+                if let Some(on_focus_changed) = self.on_focus_changed.as_mut() {
+                    on_focus_changed(engine, &mut self.state, focused);
                 }
             }
             WindowEvent::RedrawRequested => {
+                if self.minimized || engine.is_suspended() {
+                    return;
+                }
+
                 log!("---------------------------------");
+                let fixed_steps = engine.update_clock();
+                if let Some(on_fixed_update) = self.on_fixed_update.as_mut() {
+                    for _ in 0..fixed_steps {
+                        on_fixed_update(engine, &mut self.state);
+                    }
+                }
                 (self.on_new_frame)(engine, &mut self.state);
+
+                let cursor_mode = engine.get_cursor_mode();
+                if self.focused
+                    && cursor_mode != self.applied_cursor_mode
+                    && apply_cursor_mode(window, cursor_mode)
+                {
+                    self.applied_cursor_mode = cursor_mode;
+                }
+
                 engine.pre_render();
                 engine.render(window);
+                engine.end_frame();
 
-                self.window.as_ref().unwrap().request_redraw();
+                window.request_redraw();
             }
             WindowEvent::CloseRequested => {
                 log!("WindowEvent: CloseRequested");
+                self.shutdown();
                 event_loop.exit();
             }
             _ => (),
         }
     }
 }
+
+// Returns false when the grab failed, e.g. the window isn't focused yet, so
+// the caller can try again on the next frame
+fn apply_cursor_mode(window: &Window, cursor_mode: CursorMode) -> bool {
+    let grab_result = match cursor_mode {
+        CursorMode::Normal | CursorMode::Hidden => window.set_cursor_grab(CursorGrabMode::None),
+        // Not every platform can lock the cursor in place, confining it works everywhere else
+        CursorMode::Grabbed => window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined)),
+    };
+    if let Err(err) = grab_result {
+        log!(format!(
+            "Failed to set cursor mode {:?}: {}",
+            cursor_mode, err
+        ));
+        return false;
+    }
+    window.set_cursor_visible(cursor_mode == CursorMode::Normal);
+    true
+}
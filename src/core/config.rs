@@ -0,0 +1,74 @@
+use crate::vulkan_backend::{
+    config::VBackendConfig, device::config::VPhysicalDeviceConfig, instance::VInstanceConfig,
+    swapchain::config::VSwapchainConfig,
+};
+
+#[derive(Clone, Debug)]
+pub struct EngineConfig {
+    pub title: String,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub enable_validation: bool,
+    // Matched against the device name, e.g. "nvidia" or "llvmpipe"
+    pub preferred_gpu: Option<String>,
+    // Opt-in fixed timestep, in updates per second
+    pub fixed_update_rate: Option<f32>,
+    pub max_fixed_steps_per_frame: u32,
+    // Input recording written on shutdown
+    pub record_input_path: Option<String>,
+    // Input recording replayed instead of live input
+    pub replay_input_path: Option<String>,
+}
+
+impl EngineConfig {
+    pub fn get_backend_config(&self) -> VBackendConfig {
+        VBackendConfig {
+            instance_config: self.get_instance_config(),
+            physical_device_config: VPhysicalDeviceConfig {
+                preferred_device_name: self.preferred_gpu.clone(),
+                ..VPhysicalDeviceConfig::default()
+            },
+            swapchain_config: VSwapchainConfig { vsync: self.vsync },
+        }
+    }
+
+    pub fn get_headless_backend_config(&self) -> VBackendConfig {
+        VBackendConfig {
+            instance_config: self.get_instance_config(),
+            physical_device_config: VPhysicalDeviceConfig {
+                preferred_device_name: self.preferred_gpu.clone(),
+                ..VPhysicalDeviceConfig::headless()
+            },
+            swapchain_config: VSwapchainConfig { vsync: self.vsync },
+        }
+    }
+
+    fn get_instance_config(&self) -> VInstanceConfig {
+        VInstanceConfig {
+            application_name: self.title.clone(),
+            enable_validation: self.enable_validation,
+            enable_debug: self.enable_validation,
+            ..VInstanceConfig::default()
+        }
+    }
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            title: "Benzene".into(),
+            width: 1280,
+            height: 720,
+            fullscreen: false,
+            vsync: true,
+            enable_validation: cfg!(debug_assertions),
+            preferred_gpu: None,
+            fixed_update_rate: None,
+            max_fixed_steps_per_frame: 5,
+            record_input_path: None,
+            replay_input_path: None,
+        }
+    }
+}
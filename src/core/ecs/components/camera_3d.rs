@@ -1,34 +1,189 @@
-use nalgebra::Vector3;
+use ash::vk::Extent2D;
+use nalgebra::Matrix4;
 
-use crate::core::ecs::{
-    components::Transform3D,
-    types::{CursorMovedEvent, KeyboardInputEvent},
-};
+use crate::core::ecs::types::{KeyboardInputEvent, MouseMotionEvent, MouseWheelEvent};
 
+// Matrices are for Vulkan clip space: Y points down and depth goes from 0 to 1
+#[derive(Clone, Debug)]
+pub enum Projection {
+    // fov_y is the vertical field of view in radians
+    Perspective { fov_y: f32, near: f32, far: f32 },
+    // size is the height of the view in world units, the width follows the
+    // aspect ratio
+    Orthographic { size: f32, near: f32, far: f32 },
+    // Depth is 1 at near and 0 at infinity, which keeps far away geometry
+    // from fighting. Drawn with a reversed depth test.
+    ReverseZ { fov_y: f32, near: f32 },
+    // Used as is, with the regular depth test
+    Custom(Matrix4<f32>),
+}
+
+impl Projection {
+    pub fn is_reverse_z(&self) -> bool {
+        matches!(self, Self::ReverseZ { .. })
+    }
+
+    pub fn get_matrix(&self, image_extent: Extent2D) -> Matrix4<f32> {
+        let aspect = (image_extent.width as f32).max(1.0) / (image_extent.height as f32).max(1.0);
+        let mut matrix = Matrix4::zeros();
+        match *self {
+            Self::Perspective { fov_y, near, far } => {
+                let f = 1.0 / (fov_y / 2.0).tan();
+                matrix[(0, 0)] = f / aspect;
+                matrix[(1, 1)] = -f;
+                matrix[(2, 2)] = far / (near - far);
+                matrix[(2, 3)] = near * far / (near - far);
+                matrix[(3, 2)] = -1.0;
+            }
+            Self::Orthographic { size, near, far } => {
+                let half_height = size / 2.0;
+                matrix[(0, 0)] = 1.0 / (half_height * aspect);
+                matrix[(1, 1)] = -1.0 / half_height;
+                matrix[(2, 2)] = 1.0 / (near - far);
+                matrix[(2, 3)] = near / (near - far);
+                matrix[(3, 3)] = 1.0;
+            }
+            // The far plane's terms at their limit
+            Self::ReverseZ { fov_y, near } => {
+                let f = 1.0 / (fov_y / 2.0).tan();
+                matrix[(0, 0)] = f / aspect;
+                matrix[(1, 1)] = -f;
+                matrix[(2, 3)] = near;
+                matrix[(3, 2)] = -1.0;
+            }
+            Self::Custom(custom) => matrix = custom,
+        }
+        matrix
+    }
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self::Perspective {
+            fov_y: std::f32::consts::FRAC_PI_3,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+}
+
+// The camera looks down -Z of its entity's `Transform3D`, an entity without
+// one sees from its parent, or from the origin. The entity's
+// `CameraController` moves it, it flies without one.
 pub struct Camera3D {
-    pub transform: Transform3D,
+    pub projection: Projection,
     pub speed: f32,
-    pub rotation_speed: f32,
+    // Radians of rotation per unit of raw mouse motion
+    pub sensitivity: f32,
+    pub invert_y: bool,
     pub ki_events: Vec<KeyboardInputEvent>,
-    pub cm_events: Vec<CursorMovedEvent>,
+    pub mm_events: Vec<MouseMotionEvent>,
+    pub mw_events: Vec<MouseWheelEvent>,
 }
 
 impl Camera3D {
-    pub fn new(transform: Transform3D) -> Self {
+    pub fn new(projection: Projection) -> Self {
         Self {
-            transform,
+            projection,
             speed: 5.0,
-            rotation_speed: 0.1,
+            sensitivity: 0.002,
+            invert_y: false,
             ki_events: vec![],
-            cm_events: vec![]
+            mm_events: vec![],
+            mw_events: vec![],
         }
     }
 
     pub fn new_default() -> Self {
-        Self::new(Transform3D::new(
-            Vector3::new(0.0, 1.0, 5.0),
-            Vector3::new(0.0, 0.0, 0.0),
-            Vector3::new(1.0, 1.0, 1.0),
-        ))
+        Self::new(Projection::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector4;
+
+    use super::*;
+
+    const EXTENT: Extent2D = Extent2D {
+        width: 200,
+        height: 100,
+    };
+
+    // Depth after the perspective divide of a point on the view axis
+    fn get_depth(projection: &Projection, distance: f32) -> f32 {
+        let clip = projection.get_matrix(EXTENT) * Vector4::new(0.0, 0.0, -distance, 1.0);
+        clip.z / clip.w
+    }
+
+    #[test]
+    fn perspective_maps_near_and_far_to_zero_and_one() {
+        let projection = Projection::Perspective {
+            fov_y: 60f32.to_radians(),
+            near: 0.5,
+            far: 50.0,
+        };
+        assert!(get_depth(&projection, 0.5).abs() < 1e-6);
+        assert!((get_depth(&projection, 50.0) - 1.0).abs() < 1e-6);
+        assert!(get_depth(&projection, 10.0) < get_depth(&projection, 20.0));
+        assert!(!projection.is_reverse_z());
+    }
+
+    #[test]
+    fn reverse_z_maps_near_to_one_and_infinity_to_zero() {
+        let projection = Projection::ReverseZ {
+            fov_y: 60f32.to_radians(),
+            near: 0.5,
+        };
+        assert!((get_depth(&projection, 0.5) - 1.0).abs() < 1e-6);
+        assert!(get_depth(&projection, 1e6) < 1e-5);
+        assert!(get_depth(&projection, 10.0) > get_depth(&projection, 20.0));
+        assert!(projection.is_reverse_z());
+    }
+
+    #[test]
+    fn reverse_z_projects_like_perspective() {
+        let fov_y = 60f32.to_radians();
+        let perspective = Projection::Perspective {
+            fov_y,
+            near: 0.1,
+            far: 100.0,
+        }
+        .get_matrix(EXTENT);
+        let reverse_z = Projection::ReverseZ { fov_y, near: 0.1 }.get_matrix(EXTENT);
+        let point = Vector4::new(1.0, 2.0, -5.0, 1.0);
+        let (a, b) = (perspective * point, reverse_z * point);
+        assert!((a.x / a.w - b.x / b.w).abs() < 1e-6);
+        assert!((a.y / a.w - b.y / b.w).abs() < 1e-6);
+    }
+
+    #[test]
+    fn y_points_down_and_aspect_widens_x() {
+        let matrix = Projection::default().get_matrix(EXTENT);
+        assert!(matrix[(1, 1)] < 0.0);
+        assert!((matrix[(0, 0)] * 2.0 + matrix[(1, 1)]).abs() < 1e-6);
+
+        // size is the visible height
+        let orthographic = Projection::Orthographic {
+            size: 8.0,
+            near: 1.0,
+            far: 11.0,
+        };
+        let top = orthographic.get_matrix(EXTENT) * Vector4::new(8.0, 4.0, -6.0, 1.0);
+        assert!((top.x - 1.0).abs() < 1e-6);
+        assert!((top.y + 1.0).abs() < 1e-6);
+        assert!((top.z - 0.5).abs() < 1e-6);
+        assert_eq!(top.w, 1.0);
+    }
+
+    #[test]
+    fn custom_is_used_as_is_and_empty_extents_do_not_divide_by_zero() {
+        let custom = Matrix4::new_scaling(3.0);
+        assert_eq!(Projection::Custom(custom).get_matrix(EXTENT), custom);
+        let matrix = Projection::default().get_matrix(Extent2D {
+            width: 0,
+            height: 0,
+        });
+        assert!(matrix.iter().all(|value| value.is_finite()));
     }
 }
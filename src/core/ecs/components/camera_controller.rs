@@ -0,0 +1,255 @@
+use nalgebra::{UnitQuaternion, Vector3};
+
+use crate::core::ecs::entities::entity::Entity;
+
+// Picks what moves the camera on the same entity. A camera without one flies.
+// Orbit and follow place the camera in world space, so it should not have a
+// parent.
+pub enum CameraController {
+    // WASD plus mouse-look, see `camera_3d_compute_transform`
+    Fly,
+    Orbit(OrbitController),
+    Follow(FollowController),
+    Path(PathController),
+}
+
+impl CameraController {
+    // The entity it looks at, if any
+    pub fn get_target(&self) -> Option<Entity> {
+        match self {
+            Self::Orbit(orbit) => Some(orbit.target),
+            Self::Follow(follow) => Some(follow.target),
+            Self::Fly | Self::Path(_) => None,
+        }
+    }
+}
+
+// Mouse-look turns the camera around the target, the wheel zooms in and out
+pub struct OrbitController {
+    pub target: Entity,
+    pub distance: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    // Part of the distance covered per wheel step
+    pub zoom_speed: f32,
+    // Radians around the world up and the camera's right axis; (0, 0) looks
+    // down -Z at the target
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Entity, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            min_distance: 0.5,
+            max_distance: 100.0,
+            zoom_speed: 0.1,
+            yaw: 0.0,
+            pitch: 0.0,
+        }
+    }
+}
+
+// Trails the target on a damped spring, from wherever the camera starts
+pub struct FollowController {
+    pub target: Entity,
+    // Where the camera wants to be and what it looks at, in the target's
+    // space, so it stays behind the target as it turns
+    pub offset: Vector3<f32>,
+    pub look_offset: Vector3<f32>,
+    // Pull towards the offset; higher catches up faster
+    pub stiffness: f32,
+    // 2 * sqrt(stiffness) settles fastest without overshooting
+    pub damping: f32,
+    pub velocity: Vector3<f32>,
+}
+
+impl FollowController {
+    pub fn new(target: Entity, offset: Vector3<f32>) -> Self {
+        let stiffness = 30.0;
+        Self {
+            target,
+            offset,
+            look_offset: Vector3::zeros(),
+            stiffness,
+            damping: 2.0 * f32::sqrt(stiffness),
+            velocity: Vector3::zeros(),
+        }
+    }
+}
+
+// Pose of a path camera at a point in time, in seconds from the start
+#[derive(Clone, Debug)]
+pub struct CameraKeyframe {
+    pub time: f32,
+    pub position: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+}
+
+// Plays keyframes back, e.g. for cutscenes. Positions follow a smooth curve
+// through every keyframe, rotations turn evenly between them.
+pub struct PathController {
+    // Sorted by time
+    keyframes: Vec<CameraKeyframe>,
+    pub time: f32,
+    // Playback rate, 1 is real time
+    pub speed: f32,
+    pub looping: bool,
+    pub playing: bool,
+}
+
+impl PathController {
+    pub fn new(mut keyframes: Vec<CameraKeyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "camera path has no keyframes");
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Self {
+            keyframes,
+            time: 0.0,
+            speed: 1.0,
+            looping: false,
+            playing: true,
+        }
+    }
+
+    pub fn get_keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        self.keyframes.last().unwrap().time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.get_duration()
+    }
+
+    // Moves the playhead, wrapping around or stopping at the end
+    pub fn advance(&mut self, dt: f32) {
+        if !self.playing {
+            return;
+        }
+        self.time += dt * self.speed;
+        let duration = self.get_duration();
+        if self.looping && duration > 0.0 {
+            self.time = self.time.rem_euclid(duration);
+        } else {
+            self.time = self.time.clamp(0.0, duration);
+        }
+    }
+
+    // Catmull-Rom through the positions, slerp between the rotations
+    pub fn get_pose(&self, time: f32) -> (Vector3<f32>, UnitQuaternion<f32>) {
+        let keyframes = &self.keyframes;
+        let next = keyframes.partition_point(|keyframe| keyframe.time <= time);
+        if next == 0 {
+            return (keyframes[0].position, keyframes[0].rotation);
+        }
+        if next == keyframes.len() {
+            let last = &keyframes[next - 1];
+            return (last.position, last.rotation);
+        }
+
+        let (from, to) = (&keyframes[next - 1], &keyframes[next]);
+        let t = (time - from.time) / (to.time - from.time);
+        // The ends repeat their own keyframe as the missing neighbour
+        let before = &keyframes[next.saturating_sub(2)];
+        let after = &keyframes[(next + 1).min(keyframes.len() - 1)];
+        let (p0, p1, p2, p3) = (before.position, from.position, to.position, after.position);
+        let (t2, t3) = (t * t, t * t * t);
+        let position = 0.5
+            * (2.0 * p1
+                + (p2 - p0) * t
+                + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3);
+        let rotation = from
+            .rotation
+            .try_slerp(&to.rotation, t, f32::EPSILON)
+            .unwrap_or(if t < 0.5 { from.rotation } else { to.rotation });
+        (position, rotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f32, x: f32, yaw: f32) -> CameraKeyframe {
+        CameraKeyframe {
+            time,
+            position: Vector3::new(x, 0.0, 0.0),
+            rotation: UnitQuaternion::from_euler_angles(0.0, yaw, 0.0),
+        }
+    }
+
+    #[test]
+    fn keyframes_are_sorted_and_hit_exactly() {
+        let path = PathController::new(vec![
+            keyframe(2.0, 4.0, 1.0),
+            keyframe(0.0, 0.0, 0.0),
+            keyframe(1.0, 1.0, 0.5),
+        ]);
+        assert_eq!(path.get_duration(), 2.0);
+        for keyframe in path.get_keyframes() {
+            let (position, rotation) = path.get_pose(keyframe.time);
+            assert!((position - keyframe.position).norm() < 1e-6);
+            assert!(rotation.angle_to(&keyframe.rotation) < 1e-6);
+        }
+    }
+
+    #[test]
+    fn pose_is_clamped_outside_the_keyframes() {
+        let path = PathController::new(vec![keyframe(1.0, 1.0, 0.2), keyframe(3.0, 5.0, 0.8)]);
+        assert_eq!(path.get_pose(-1.0).0, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(path.get_pose(0.0).0, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(path.get_pose(10.0).0, Vector3::new(5.0, 0.0, 0.0));
+
+        let single = PathController::new(vec![keyframe(0.0, 2.0, 0.3)]);
+        assert_eq!(single.get_pose(0.5).0, Vector3::new(2.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn pose_is_smooth_between_keyframes() {
+        // Away from the ends, evenly spaced points on a line are passed at
+        // constant speed
+        let path = PathController::new(vec![
+            keyframe(0.0, 0.0, 0.0),
+            keyframe(1.0, 1.0, 0.0),
+            keyframe(2.0, 2.0, 1.0),
+            keyframe(3.0, 3.0, 1.0),
+        ]);
+        for time in [1.25, 1.5, 1.75] {
+            assert!((path.get_pose(time).0.x - time).abs() < 1e-5);
+        }
+        // and never backwards at the ends
+        let xs: Vec<f32> = (0..=30)
+            .map(|step| path.get_pose(step as f32 * 0.1).0.x)
+            .collect();
+        assert!(xs.windows(2).all(|pair| pair[1] >= pair[0]));
+        // Rotation turns evenly between its two keyframes
+        let (_, rotation) = path.get_pose(1.5);
+        assert!(rotation.angle_to(&UnitQuaternion::from_euler_angles(0.0, 0.5, 0.0)) < 1e-5);
+    }
+
+    #[test]
+    fn advance_stops_or_loops_at_the_end() {
+        let mut path = PathController::new(vec![keyframe(0.0, 0.0, 0.0), keyframe(2.0, 1.0, 0.0)]);
+        path.advance(1.5);
+        assert!(!path.is_finished());
+        path.advance(1.5);
+        assert_eq!(path.time, 2.0);
+        assert!(path.is_finished());
+
+        path.time = 0.0;
+        path.looping = true;
+        path.speed = 2.0;
+        path.advance(1.5);
+        assert!((path.time - 1.0).abs() < 1e-6);
+        assert!(!path.is_finished());
+
+        path.playing = false;
+        path.advance(1.0);
+        assert!((path.time - 1.0).abs() < 1e-6);
+    }
+}
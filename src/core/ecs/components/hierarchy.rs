@@ -0,0 +1,23 @@
+use crate::core::ecs::entities::entity::Entity;
+
+// Both sides of the relation are kept in sync by `Scene::set_parent` and
+// `Scene::remove_parent`, so they can't be built outside the crate
+pub struct Parent {
+    pub(crate) id: Entity,
+}
+
+impl Parent {
+    pub fn get_id(&self) -> &Entity {
+        &self.id
+    }
+}
+
+pub struct Children {
+    pub(crate) ids: Vec<Entity>,
+}
+
+impl Children {
+    pub fn get_ids(&self) -> &[Entity] {
+        &self.ids
+    }
+}
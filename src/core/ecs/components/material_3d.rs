@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use ash::vk;
 
 use crate::{
@@ -5,8 +7,14 @@ use crate::{
     vulkan_backend::descriptor::VDescriptorWriteBatch,
 };
 
+#[derive(Clone)]
 pub struct Material3D {
     pub manager_index: usize,
+    // Image the texture was loaded from, None for the default material
+    pub texture_path: Option<String>,
+    // Held by every copy of a material loaded for a scene or prefab file.
+    // The engine frees the material and its texture once only it is left.
+    pub(crate) users: Option<Arc<()>>,
 }
 
 impl Material3D {
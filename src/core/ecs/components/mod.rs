@@ -1,13 +1,19 @@
 pub mod camera_3d;
+pub mod camera_controller;
 pub mod directional_light_3d;
+pub mod hierarchy;
 pub mod material_3d;
 pub mod point_light_3d;
 pub mod spot_light_3d;
 pub mod structure_3d;
+pub mod tags;
 pub mod transform_3d;
 
-pub use camera_3d::Camera3D;
+pub use camera_3d::{Camera3D, Projection};
+pub use camera_controller::CameraController;
+pub use hierarchy::{Children, Parent};
 pub use material_3d::Material3D;
 pub use point_light_3d::PointLight3D;
 pub use structure_3d::Structure3D;
+pub use tags::Tags;
 pub use transform_3d::Transform3D;
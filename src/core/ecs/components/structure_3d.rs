@@ -1,22 +1,18 @@
+use std::sync::Arc;
+
 use crate::core::gpu::model::Model;
-use crate::vulkan_backend::backend::VBackend;
 
+// Clones share the model, e.g. every instance of a prefab. Made by the
+// engine, which frees the model once no structure holds it anymore.
+#[derive(Clone)]
 pub struct Structure3D {
-    pub model: Model,
+    pub model: Arc<Model>,
+    // Where the model was loaded from, needed to save the scene
+    pub asset_path: Option<String>,
 }
 
 impl Structure3D {
-    pub fn new(model: Model) -> Self {
-        Self { model }
-    }
-
-    pub fn from_obj(v_backend: &VBackend, obj_path: &str) -> Self {
-        Self {
-            model: Model::from_obj(v_backend, obj_path),
-        }
-    }
-
-    pub fn destroy(&self, v_backend: &VBackend) {
-        self.model.destroy(v_backend);
+    pub(crate) fn new(model: Arc<Model>, asset_path: Option<String>) -> Self {
+        Self { model, asset_path }
     }
 }
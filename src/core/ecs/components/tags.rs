@@ -0,0 +1,33 @@
+// Free-form labels for grouping entities, see `Scene::find_all_with_tag`
+#[derive(Clone, Debug)]
+pub struct Tags {
+    names: Vec<String>,
+}
+
+impl Tags {
+    pub fn new(names: &[&str]) -> Self {
+        let mut tags = Self { names: vec![] };
+        for name in names {
+            tags.add(name);
+        }
+        tags
+    }
+
+    pub fn add(&mut self, name: &str) {
+        if !self.has(name) {
+            self.names.push(name.into());
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.names.retain(|tag| tag != name);
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.names.iter().any(|tag| tag == name)
+    }
+
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+}
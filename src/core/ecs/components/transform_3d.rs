@@ -1,23 +1,37 @@
-use ash::vk::Extent2D;
-use nalgebra::{Matrix4, Perspective3, Rotation3, Translation3, Vector3};
+use nalgebra::{Matrix4, Rotation3, UnitQuaternion, Vector3};
 
 #[derive(Clone, Debug)]
 pub struct Transform3D {
     pub position: Vector3<f32>,
-    pub rotation: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
     pub scale: Vector3<f32>,
+    // Relative to the parent, or to the world for entities without one
     pub cached_transform: Matrix4<f32>,
-    pub dirty: bool,
+    // Parent's world transform times `cached_transform`, see `Scene::set_parent`
+    pub world_transform: Matrix4<f32>,
 }
 
 impl Transform3D {
+    // rotation contains Euler angles as (roll, pitch, yaw)
     pub fn new(position: Vector3<f32>, rotation: Vector3<f32>, scale: Vector3<f32>) -> Self {
+        Self::new_with_rotation(
+            position,
+            UnitQuaternion::from_euler_angles(rotation.x, rotation.y, rotation.z),
+            scale,
+        )
+    }
+
+    pub fn new_with_rotation(
+        position: Vector3<f32>,
+        rotation: UnitQuaternion<f32>,
+        scale: Vector3<f32>,
+    ) -> Self {
         Self {
             position,
             rotation,
             scale,
             cached_transform: Matrix4::identity(),
-            dirty: true,
+            world_transform: Matrix4::identity(),
         }
     }
     pub fn new_default() -> Self {
@@ -29,27 +43,34 @@ impl Transform3D {
     }
 
     pub fn get_rotation3(&self) -> Rotation3<f32> {
-        let rot = self.rotation;
-        Rotation3::from_euler_angles(rot.x, rot.y, rot.z)
-    }
-
-    pub fn get_transform_3d_view_projection(
-        &self,
-        image_extent: Extent2D,
-    ) -> (Matrix4<f32>, Matrix4<f32>) {
-        let pos = self.position;
-        let r_inv = self.get_rotation3().inverse();
-        let t_inv = Translation3::new(-pos.x, -pos.y, -pos.z);
-        let view = r_inv.to_homogeneous() * t_inv.to_homogeneous();
-
-        // Projection (Vulkan NDC requires Y flip)
-        let aspect = (image_extent.width as f32).max(1.0) / (image_extent.height as f32).max(1.0);
-        let fovy = std::f32::consts::FRAC_PI_3; // 60 degrees
-        let znear = 0.1_f32;
-        let zfar = 100.0_f32;
-        let mut projection = Perspective3::new(aspect, fovy, znear, zfar).to_homogeneous();
-        projection[(1, 1)] *= -1.0;
-
-        (view, projection)
+        self.rotation.to_rotation_matrix()
+    }
+
+    // (roll, pitch, yaw), the inverse of what `new` takes
+    pub fn get_euler_angles(&self) -> Vector3<f32> {
+        let (roll, pitch, yaw) = self.rotation.euler_angles();
+        Vector3::new(roll, pitch, yaw)
+    }
+
+    // Local axes in parent space; forward is -Z, like cameras and lights
+    pub fn get_forward(&self) -> Vector3<f32> {
+        self.rotation * -Vector3::z()
+    }
+
+    pub fn get_right(&self) -> Vector3<f32> {
+        self.rotation * Vector3::x()
+    }
+
+    pub fn get_up(&self) -> Vector3<f32> {
+        self.rotation * Vector3::y()
+    }
+
+    pub fn get_world_position(&self) -> Vector3<f32> {
+        self.world_transform.fixed_view::<3, 1>(0, 3).into()
+    }
+
+    // Where a local direction, e.g. -Z for lights, points in world space
+    pub fn get_world_direction(&self, local: Vector3<f32>) -> Vector3<f32> {
+        self.world_transform.transform_vector(&local).normalize()
     }
 }
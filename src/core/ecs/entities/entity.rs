@@ -0,0 +1,135 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+// A slot index plus the generation of that slot. Despawning bumps the
+// generation, so handles to the old occupant no longer match anything.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    pub fn get_index(&self) -> u32 {
+        self.index
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+// Short form for logs, e.g. `#3v1`
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}v{}", self.index, self.generation)
+    }
+}
+
+// Hands out entities per scene. Freed slots are reused lowest index first,
+// so spawning the same way always yields the same entities.
+#[derive(Default)]
+pub struct EntityAllocator {
+    generations: Vec<u32>,
+    is_alive: Vec<bool>,
+    free: Vec<u32>,
+}
+
+impl EntityAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allocate(&mut self) -> Entity {
+        // Kept sorted descending, the lowest free index is last
+        if let Some(index) = self.free.pop() {
+            self.is_alive[index as usize] = true;
+            return Entity {
+                index,
+                generation: self.generations[index as usize],
+            };
+        }
+
+        let index = self.generations.len() as u32;
+        self.generations.push(0);
+        self.is_alive.push(true);
+        Entity {
+            index,
+            generation: 0,
+        }
+    }
+
+    // Returns false for entities that were already freed
+    pub fn free(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        let index = entity.index as usize;
+        self.is_alive[index] = false;
+        self.generations[index] = self.generations[index].wrapping_add(1);
+        let position = self
+            .free
+            .partition_point(|free_index| *free_index > entity.index);
+        self.free.insert(position, entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let index = entity.index as usize;
+        index < self.generations.len()
+            && self.is_alive[index]
+            && self.generations[index] == entity.generation
+    }
+
+    pub fn get_alive_count(&self) -> usize {
+        self.generations.len() - self.free.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn freed_entities_are_rejected_by_generation() {
+        let mut allocator = EntityAllocator::new();
+        let first = allocator.allocate();
+        assert!(allocator.free(first));
+        assert!(!allocator.free(first));
+
+        let reused = allocator.allocate();
+        assert_eq!(reused.get_index(), first.get_index());
+        assert_eq!(reused.get_generation(), first.get_generation() + 1);
+        assert!(!allocator.is_alive(first));
+        assert!(allocator.is_alive(reused));
+        assert!(!allocator.free(first));
+        assert!(allocator.is_alive(reused));
+    }
+
+    #[test]
+    fn lowest_free_index_is_reused_first() {
+        let mut allocator = EntityAllocator::new();
+        let entities: Vec<Entity> = (0..5).map(|_| allocator.allocate()).collect();
+        for index in [3, 1, 4] {
+            allocator.free(entities[index]);
+        }
+        assert_eq!(allocator.get_alive_count(), 2);
+
+        let indices: Vec<u32> = (0..4).map(|_| allocator.allocate().get_index()).collect();
+        assert_eq!(indices, vec![1, 3, 4, 5]);
+        assert_eq!(allocator.get_alive_count(), 6);
+    }
+
+    #[test]
+    fn out_of_range_entities_are_not_alive() {
+        let allocator = EntityAllocator::new();
+        let entity = Entity {
+            index: 7,
+            generation: 0,
+        };
+        assert!(!allocator.is_alive(entity));
+        assert_eq!(entity.to_string(), "#7v0");
+    }
+}
@@ -1,20 +1,36 @@
-use crate::shared::types::Id;
+use std::fmt;
 
+use crate::core::ecs::entities::entity::Entity;
+
+// An entity plus the name it was spawned with, see `Scene::spawn`
 #[derive(Clone)]
 pub struct GameObject {
-    id: Id,
+    id: Entity,
     pub name: String,
 }
 
 impl GameObject {
-    pub fn new(name: &str) -> Self {
-        return Self {
-            id: rand::random(),
+    pub(crate) fn new(id: Entity, name: &str) -> Self {
+        Self {
+            id,
             name: name.into(),
-        };
+        }
     }
 
-    pub fn get_id(&self) -> &[u8; 64] {
+    pub fn get_id(&self) -> &Entity {
         &self.id
     }
 }
+
+// Printed by name, e.g. `"Camera" #0v0`
+impl fmt::Debug for GameObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.name, self.id)
+    }
+}
+
+impl fmt::Display for GameObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
@@ -0,0 +1,246 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::core::ecs::entities::entity::Entity;
+
+// Published by the engine on the bus of the active scene
+#[derive(Clone, Debug, PartialEq)]
+pub enum EngineEvent {
+    // The render target was recreated with a new extent
+    Resized { width: u32, height: u32 },
+    // The scene became the active one
+    SceneActivated,
+}
+
+// Sent by `Scene::despawn` for every entity it removes
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntityDespawned {
+    pub entity: Entity,
+    pub name: String,
+}
+
+// Events of one type, double buffered. An event is readable during the
+// update it was sent in and the next one, then dropped by `update`.
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+    // Number of events sent before the first one of each buffer
+    previous_start: usize,
+    current_start: usize,
+}
+
+// Not derived, which would require `T: Default`
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Self {
+            previous: vec![],
+            current: vec![],
+            previous_start: 0,
+            current_start: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    // Drops the events of the previous update
+    pub fn update(&mut self) {
+        self.previous_start = self.current_start;
+        self.current_start += self.current.len();
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    // Every buffered event, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(self.current.iter())
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_sent_count(&self) -> usize {
+        self.current_start + self.current.len()
+    }
+}
+
+// Remembers which events were read already, so a system that keeps one sees
+// every event exactly once. Keep one per reading system, e.g. captured in the
+// system closure.
+pub struct EventReader<T> {
+    read_count: usize,
+    _event: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> EventReader<T> {
+    pub fn new() -> Self {
+        Self {
+            read_count: 0,
+            _event: PhantomData,
+        }
+    }
+
+    // Events sent since the last call that are still buffered
+    pub fn read<'e>(&mut self, events: &'e Events<T>) -> impl Iterator<Item = &'e T> + use<'e, T> {
+        let skipped = self.read_count.saturating_sub(events.previous_start);
+        self.read_count = events.get_sent_count();
+        events.iter().skip(skipped)
+    }
+}
+
+// Type-erased `Events<T>` so the bus can update every channel
+trait EventChannel: Any {
+    fn update(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> EventChannel for Events<T> {
+    fn update(&mut self) {
+        Events::update(self);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// One channel per event type, keyed by the event's `TypeId`
+#[derive(Default)]
+pub struct EventBus {
+    channels: HashMap<TypeId, Box<dyn EventChannel>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send<T: 'static>(&mut self, event: T) {
+        self.get_events_mut::<T>().send(event);
+    }
+
+    // Creates the channel on first use
+    pub fn get_events_mut<T: 'static>(&mut self) -> &mut Events<T> {
+        self.channels
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Events::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<Events<T>>()
+            .expect("event channel has the wrong type")
+    }
+
+    pub fn get_events<T: 'static>(&self) -> Option<&Events<T>> {
+        self.channels
+            .get(&TypeId::of::<T>())
+            .and_then(|channel| channel.as_any().downcast_ref::<Events<T>>())
+    }
+
+    pub fn read<'e, T: 'static>(
+        &'e self,
+        reader: &mut EventReader<T>,
+    ) -> impl Iterator<Item = &'e T> + use<'e, T> {
+        self.get_events::<T>()
+            .map(|events| reader.read(events))
+            .into_iter()
+            .flatten()
+    }
+
+    pub fn update(&mut self) {
+        for channel in self.channels.values_mut() {
+            channel.update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_all(reader: &mut EventReader<u32>, events: &Events<u32>) -> Vec<u32> {
+        reader.read(events).copied().collect()
+    }
+
+    #[test]
+    fn events_live_for_two_updates() {
+        let mut events = Events::new();
+        events.send(1);
+        assert_eq!(events.len(), 1);
+        events.update();
+        events.send(2);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        events.update();
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), vec![2]);
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn reader_sees_every_event_once() {
+        let mut events = Events::new();
+        let mut reader = EventReader::new();
+        events.send(1);
+        events.send(2);
+        assert_eq!(read_all(&mut reader, &events), vec![1, 2]);
+        assert!(read_all(&mut reader, &events).is_empty());
+
+        // Still buffered after the update, but already read
+        events.update();
+        events.send(3);
+        assert_eq!(read_all(&mut reader, &events), vec![3]);
+        events.update();
+        events.update();
+        events.send(4);
+        assert_eq!(read_all(&mut reader, &events), vec![4]);
+    }
+
+    #[test]
+    fn late_reader_only_sees_what_is_still_buffered() {
+        let mut events = Events::new();
+        for event in 0..3 {
+            events.send(event);
+            events.update();
+        }
+        events.send(3);
+
+        let mut reader = EventReader::new();
+        assert_eq!(read_all(&mut reader, &events), vec![2, 3]);
+    }
+
+    #[test]
+    fn bus_keeps_a_channel_per_type() {
+        let mut bus = EventBus::new();
+        let mut reader = EventReader::<u32>::new();
+        assert_eq!(bus.read(&mut reader).count(), 0);
+
+        bus.send(7u32);
+        bus.send("other");
+        assert_eq!(bus.read(&mut reader).copied().collect::<Vec<_>>(), vec![7]);
+        bus.update();
+        bus.update();
+        assert!(bus.get_events::<u32>().unwrap().is_empty());
+        assert!(bus.get_events::<&str>().unwrap().is_empty());
+    }
+}
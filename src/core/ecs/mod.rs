@@ -1,4 +1,8 @@
 pub mod components;
 pub mod entities;
+pub mod events;
+pub mod query;
+pub mod schedule;
+pub mod storage;
 pub mod systems;
 pub mod types;
@@ -0,0 +1,295 @@
+use std::any::{TypeId, type_name};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::core::ecs::{
+    entities::entity::Entity,
+    storage::{ComponentColumn, ComponentRegistry, SparseSet, SparseSlots},
+};
+
+#[derive(Clone)]
+pub struct ComponentAccess {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    pub mutable: bool,
+    // Optional components don't narrow down the matched entities
+    pub optional: bool,
+}
+
+impl ComponentAccess {
+    pub fn of<T: 'static>(mutable: bool) -> Self {
+        Self {
+            type_id: TypeId::of::<T>(),
+            type_name: type_name::<T>(),
+            mutable,
+            optional: false,
+        }
+    }
+}
+
+// A component may be borrowed mutably by one parameter only, the same rule
+// the borrow checker applies to plain references
+pub fn check_access(access: &[ComponentAccess]) {
+    for (index, first) in access.iter().enumerate() {
+        for second in access[index + 1..].iter() {
+            if first.type_id == second.type_id && (first.mutable || second.mutable) {
+                panic!(
+                    "query borrows {} mutably more than once or both mutably and immutably",
+                    first.type_name
+                );
+            }
+        }
+    }
+}
+
+// Columns borrowed by a query for as long as it lives
+pub struct QueryColumns<'w> {
+    exclusive: HashMap<TypeId, &'w mut dyn ComponentColumn>,
+    shared: HashMap<TypeId, &'w dyn ComponentColumn>,
+}
+
+impl<'w> QueryColumns<'w> {
+    pub(crate) fn new() -> Self {
+        Self {
+            exclusive: HashMap::new(),
+            shared: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn add_exclusive(&mut self, type_id: TypeId, column: &'w mut dyn ComponentColumn) {
+        self.exclusive.insert(type_id, column);
+    }
+
+    pub(crate) fn add_shared(&mut self, type_id: TypeId, column: &'w dyn ComponentColumn) {
+        self.shared.insert(type_id, column);
+    }
+
+    fn take_mut<T: 'static>(&mut self) -> Option<&'w mut SparseSet<T>> {
+        self.exclusive
+            .remove(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+    }
+
+    fn get<T: 'static>(&self) -> Option<&'w SparseSet<T>> {
+        let column: &'w dyn ComponentColumn = *self.shared.get(&TypeId::of::<T>())?;
+        column.as_any().downcast_ref::<SparseSet<T>>()
+    }
+}
+
+// What a query yields per entity: `&T`, `&mut T`, `Option<_>` of either, or
+// a tuple of those
+pub trait QueryData {
+    type Item<'w>;
+    type State<'w>;
+
+    fn register_access(access: &mut Vec<ComponentAccess>);
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool;
+    fn init_state<'w>(columns: &mut QueryColumns<'w>) -> Self::State<'w>;
+    fn fetch<'w>(state: &mut Self::State<'w>, id: &Entity) -> Option<Self::Item<'w>>;
+}
+
+// Query data that never borrows mutably, usable from a shared reference
+pub trait ReadOnlyQueryData: QueryData {}
+
+impl<T: 'static> QueryData for &T {
+    type Item<'w> = &'w T;
+    type State<'w> = Option<&'w SparseSet<T>>;
+
+    fn register_access(access: &mut Vec<ComponentAccess>) {
+        access.push(ComponentAccess::of::<T>(false));
+    }
+
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+        registry.has::<T>(id)
+    }
+
+    fn init_state<'w>(columns: &mut QueryColumns<'w>) -> Self::State<'w> {
+        columns.get::<T>()
+    }
+
+    fn fetch<'w>(state: &mut Self::State<'w>, id: &Entity) -> Option<Self::Item<'w>> {
+        state.and_then(|column| column.get(id))
+    }
+}
+
+impl<T: 'static> ReadOnlyQueryData for &T {}
+
+impl<T: 'static> QueryData for &mut T {
+    type Item<'w> = &'w mut T;
+    // Each component is handed out once, so they are split up front
+    type State<'w> = SparseSlots<'w, T>;
+
+    fn register_access(access: &mut Vec<ComponentAccess>) {
+        access.push(ComponentAccess::of::<T>(true));
+    }
+
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+        registry.has::<T>(id)
+    }
+
+    fn init_state<'w>(columns: &mut QueryColumns<'w>) -> Self::State<'w> {
+        match columns.take_mut::<T>() {
+            Some(column) => column.split_slots(),
+            None => SparseSlots::empty(),
+        }
+    }
+
+    fn fetch<'w>(state: &mut Self::State<'w>, id: &Entity) -> Option<Self::Item<'w>> {
+        state.take(id)
+    }
+}
+
+impl<D: QueryData> QueryData for Option<D> {
+    type Item<'w> = Option<D::Item<'w>>;
+    type State<'w> = D::State<'w>;
+
+    fn register_access(access: &mut Vec<ComponentAccess>) {
+        let mut inner = vec![];
+        D::register_access(&mut inner);
+        for each in inner.iter_mut() {
+            each.optional = true;
+        }
+        access.extend(inner);
+    }
+
+    fn matches(_: &ComponentRegistry, _: &Entity) -> bool {
+        true
+    }
+
+    fn init_state<'w>(columns: &mut QueryColumns<'w>) -> Self::State<'w> {
+        D::init_state(columns)
+    }
+
+    fn fetch<'w>(state: &mut Self::State<'w>, id: &Entity) -> Option<Self::Item<'w>> {
+        Some(D::fetch(state, id))
+    }
+}
+
+impl<D: ReadOnlyQueryData> ReadOnlyQueryData for Option<D> {}
+
+macro_rules! impl_query_data_tuple {
+    ($($data:ident $state:ident),+) => {
+        impl<$($data: QueryData),+> QueryData for ($($data,)+) {
+            type Item<'w> = ($($data::Item<'w>,)+);
+            type State<'w> = ($($data::State<'w>,)+);
+
+            fn register_access(access: &mut Vec<ComponentAccess>) {
+                $($data::register_access(access);)+
+            }
+
+            fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+                $($data::matches(registry, id))&&+
+            }
+
+            fn init_state<'w>(columns: &mut QueryColumns<'w>) -> Self::State<'w> {
+                ($($data::init_state(columns),)+)
+            }
+
+            fn fetch<'w>(state: &mut Self::State<'w>, id: &Entity) -> Option<Self::Item<'w>> {
+                let ($($state,)+) = state;
+                Some(($($data::fetch($state, id)?,)+))
+            }
+        }
+
+        impl<$($data: ReadOnlyQueryData),+> ReadOnlyQueryData for ($($data,)+) {}
+    };
+}
+
+impl_query_data_tuple!(A a);
+impl_query_data_tuple!(A a, B b);
+impl_query_data_tuple!(A a, B b, C c);
+impl_query_data_tuple!(A a, B b, C c, D d);
+impl_query_data_tuple!(A a, B b, C c, D d, E e);
+impl_query_data_tuple!(A a, B b, C c, D d, E e, F f);
+impl_query_data_tuple!(A a, B b, C c, D d, E e, F f, G g);
+impl_query_data_tuple!(A a, B b, C c, D d, E e, F f, G g, H h);
+
+// Narrows a query down without borrowing anything
+pub trait QueryFilter {
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool;
+}
+
+pub struct With<T>(PhantomData<T>);
+
+pub struct Without<T>(PhantomData<T>);
+
+impl QueryFilter for () {
+    fn matches(_: &ComponentRegistry, _: &Entity) -> bool {
+        true
+    }
+}
+
+impl<T: 'static> QueryFilter for With<T> {
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+        registry.has::<T>(id)
+    }
+}
+
+impl<T: 'static> QueryFilter for Without<T> {
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+        !registry.has::<T>(id)
+    }
+}
+
+// Inserted since the running system last ran; outside of systems, over the
+// last frame
+pub struct Added<T>(PhantomData<T>);
+
+// Inserted or borrowed mutably since the running system last ran; outside of
+// systems, over the last frame
+pub struct Changed<T>(PhantomData<T>);
+
+impl<T: 'static> QueryFilter for Added<T> {
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+        registry.is_added::<T>(id)
+    }
+}
+
+impl<T: 'static> QueryFilter for Changed<T> {
+    fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+        registry.is_changed::<T>(id)
+    }
+}
+
+macro_rules! impl_query_filter_tuple {
+    ($($filter:ident),+) => {
+        impl<$($filter: QueryFilter),+> QueryFilter for ($($filter,)+) {
+            fn matches(registry: &ComponentRegistry, id: &Entity) -> bool {
+                $($filter::matches(registry, id))&&+
+            }
+        }
+    };
+}
+
+impl_query_filter_tuple!(A);
+impl_query_filter_tuple!(A, B);
+impl_query_filter_tuple!(A, B, C);
+impl_query_filter_tuple!(A, B, C, D);
+
+pub struct Query<'w, D: QueryData> {
+    ids: std::vec::IntoIter<Entity>,
+    state: D::State<'w>,
+}
+
+impl<'w, D: QueryData> Query<'w, D> {
+    pub(crate) fn new(ids: Vec<Entity>, state: D::State<'w>) -> Self {
+        Self {
+            ids: ids.into_iter(),
+            state,
+        }
+    }
+}
+
+impl<'w, D: QueryData> Iterator for Query<'w, D> {
+    type Item = D::Item<'w>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for id in self.ids.by_ref() {
+            if let Some(item) = D::fetch(&mut self.state, &id) {
+                return Some(item);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+
+use crate::{
+    core::{
+        ecs::{
+            entities::entity::Entity,
+            query::{ComponentAccess, Query, QueryColumns, QueryData, QueryFilter},
+            storage::ComponentRegistry,
+        },
+        input::InputState,
+        scene::Scene,
+    },
+    log,
+    vulkan_backend::backend::VBackend,
+};
+
+// Stages run in this order every frame, from `GameEngine::pre_render`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+    PreRender,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::PreRender,
+    ];
+}
+
+pub struct SystemContext<'a> {
+    pub v_backend: &'a VBackend,
+    pub input: &'a InputState,
+    // Scaled frame time in seconds
+    pub dt: f32,
+}
+
+// What a parallel system sees besides its query. The backend is left out,
+// GPU uploads belong in exclusive systems.
+pub struct ParallelContext<'a> {
+    pub input: &'a InputState,
+    pub dt: f32,
+}
+
+pub type SystemFn = dyn FnMut(&mut Scene, &SystemContext);
+
+pub type SystemJob<'w> = Box<dyn FnOnce(&ParallelContext) + Send + 'w>;
+
+// A parallel system with its query type erased
+pub trait ParallelSystemFn: Send + Sync {
+    fn get_access(&self) -> &[ComponentAccess];
+    fn get_matching_ids(&self, registry: &ComponentRegistry) -> Vec<Entity>;
+    fn make_job<'w>(&'w self, columns: &mut QueryColumns<'w>, ids: Vec<Entity>) -> SystemJob<'w>;
+}
+
+struct ParallelSystem<D, F, Func> {
+    access: Vec<ComponentAccess>,
+    run: Func,
+    _query: PhantomData<fn() -> (D, F)>,
+}
+
+impl<D, F, Func> ParallelSystemFn for ParallelSystem<D, F, Func>
+where
+    D: QueryData + 'static,
+    F: QueryFilter + 'static,
+    Func: for<'w> Fn(Query<'w, D>, &ParallelContext) + Send + Sync + 'static,
+    for<'w> D::State<'w>: Send,
+{
+    fn get_access(&self) -> &[ComponentAccess] {
+        &self.access
+    }
+
+    fn get_matching_ids(&self, registry: &ComponentRegistry) -> Vec<Entity> {
+        registry.get_matching_ids::<D, F>(&self.access)
+    }
+
+    fn make_job<'w>(&'w self, columns: &mut QueryColumns<'w>, ids: Vec<Entity>) -> SystemJob<'w> {
+        let query = Query::<D>::new(ids, D::init_state(columns));
+        Box::new(move |ctx| (self.run)(query, ctx))
+    }
+}
+
+pub enum SystemRun {
+    // Gets the whole scene, runs alone
+    Exclusive(Box<SystemFn>),
+    // Gets one query, may run next to other parallel systems
+    Parallel(Box<dyn ParallelSystemFn>),
+}
+
+pub struct System {
+    pub name: String,
+    pub stage: Stage,
+    before: Vec<String>,
+    after: Vec<String>,
+    // None for exclusive systems
+    access: Option<Vec<ComponentAccess>>,
+    run: Option<SystemRun>,
+    // Change tick of the previous run, 0 before the first one
+    last_run_tick: u64,
+}
+
+impl System {
+    pub fn new(
+        name: &str,
+        stage: Stage,
+        run: impl FnMut(&mut Scene, &SystemContext) + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            stage,
+            before: vec![],
+            after: vec![],
+            access: None,
+            run: Some(SystemRun::Exclusive(Box::new(run))),
+            last_run_tick: 0,
+        }
+    }
+
+    // Only touches the components of its query, e.g.
+    // `System::parallel::<(&mut Transform3D, &Velocity)>(...)`, so it can run
+    // on the thread pool next to systems it doesn't conflict with
+    pub fn parallel<D>(
+        name: &str,
+        stage: Stage,
+        run: impl for<'w> Fn(Query<'w, D>, &ParallelContext) + Send + Sync + 'static,
+    ) -> Self
+    where
+        D: QueryData + 'static,
+        for<'w> D::State<'w>: Send,
+    {
+        Self::parallel_filtered::<D, ()>(name, stage, run)
+    }
+
+    pub fn parallel_filtered<D, F>(
+        name: &str,
+        stage: Stage,
+        run: impl for<'w> Fn(Query<'w, D>, &ParallelContext) + Send + Sync + 'static,
+    ) -> Self
+    where
+        D: QueryData + 'static,
+        F: QueryFilter + 'static,
+        for<'w> D::State<'w>: Send,
+    {
+        let access = ComponentRegistry::get_access::<D>();
+        Self {
+            name: name.into(),
+            stage,
+            before: vec![],
+            after: vec![],
+            access: Some(access.clone()),
+            run: Some(SystemRun::Parallel(Box::new(ParallelSystem::<D, F, _> {
+                access,
+                run,
+                _query: PhantomData,
+            }))),
+            last_run_tick: 0,
+        }
+    }
+
+    // Runs before the named system when both are in the same stage
+    pub fn before(mut self, name: &str) -> Self {
+        self.before.push(name.into());
+        self
+    }
+
+    // Runs after the named system when both are in the same stage
+    pub fn after(mut self, name: &str) -> Self {
+        self.after.push(name.into());
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<System>,
+    // Batches in run order per stage, rebuilt when systems change. Systems of
+    // one batch may run at the same time, an exclusive system is always alone.
+    stage_batches: Option<HashMap<Stage, Vec<Vec<String>>>>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Replaces a system registered under the same name
+    pub fn add_system(&mut self, system: System) {
+        if self.remove_system(&system.name) {
+            log!(format!("Replacing system {}", system.name));
+        }
+        self.systems.push(system);
+        self.stage_batches = None;
+    }
+
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|system| system.name != name);
+        self.stage_batches = None;
+        self.systems.len() != count
+    }
+
+    pub fn has_system(&self, name: &str) -> bool {
+        self.systems.iter().any(|system| system.name == name)
+    }
+
+    pub fn get_stage_batches(&mut self, stage: Stage) -> &[Vec<String>] {
+        let stage_batches = self.stage_batches.get_or_insert_with(|| {
+            Stage::ALL
+                .iter()
+                .map(|stage| (*stage, batch_stage(&self.systems, *stage)))
+                .collect()
+        });
+        &stage_batches[&stage]
+    }
+
+    // The system is taken out while it runs, so it can borrow the whole scene
+    pub fn take_run(&mut self, name: &str) -> Option<SystemRun> {
+        self.systems
+            .iter_mut()
+            .find(|system| system.name == name)?
+            .run
+            .take()
+    }
+
+    pub fn get_last_run_tick(&self, name: &str) -> u64 {
+        self.systems
+            .iter()
+            .find(|system| system.name == name)
+            .map_or(0, |system| system.last_run_tick)
+    }
+
+    // Called before `restore_run`, a replacement keeps its own tick
+    pub fn set_last_run_tick(&mut self, name: &str, tick: u64) {
+        if let Some(system) = self
+            .systems
+            .iter_mut()
+            .find(|system| system.name == name && system.run.is_none())
+        {
+            system.last_run_tick = tick;
+        }
+    }
+
+    // Dropped if the system was removed or replaced while it ran
+    pub fn restore_run(&mut self, name: &str, run: SystemRun) {
+        if let Some(system) = self
+            .systems
+            .iter_mut()
+            .find(|system| system.name == name && system.run.is_none())
+        {
+            system.run = Some(run);
+        }
+    }
+}
+
+// Walks the stage in topological order and groups consecutive parallel
+// systems that neither conflict nor are ordered against each other
+fn batch_stage(systems: &[System], stage: Stage) -> Vec<Vec<String>> {
+    let (order, edges) = sort_stage(systems, stage);
+    let get_access = |name: &String| {
+        systems
+            .iter()
+            .find(|system| system.name == *name)
+            .and_then(|system| system.access.as_ref())
+    };
+
+    let mut batches = vec![];
+    let mut current: Vec<String> = vec![];
+    for name in order {
+        let Some(access) = get_access(&name) else {
+            if !current.is_empty() {
+                batches.push(std::mem::take(&mut current));
+            }
+            batches.push(vec![name]);
+            continue;
+        };
+
+        let fits = current.iter().all(|other| {
+            !edges.contains(&(other.clone(), name.clone()))
+                && !accesses_conflict(access, get_access(other).unwrap())
+        });
+        if !fits {
+            batches.push(std::mem::take(&mut current));
+        }
+        current.push(name);
+    }
+    if !current.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
+
+fn accesses_conflict(first: &[ComponentAccess], second: &[ComponentAccess]) -> bool {
+    first.iter().any(|a| {
+        second
+            .iter()
+            .any(|b| a.type_id == b.type_id && (a.mutable || b.mutable))
+    })
+}
+
+// Topological order of the stage's systems, ties keep registration order.
+// Also returns every (first, second) ordering constraint between them.
+fn sort_stage(systems: &[System], stage: Stage) -> (Vec<String>, HashSet<(String, String)>) {
+    let stage_systems: Vec<&System> = systems
+        .iter()
+        .filter(|system| system.stage == stage)
+        .collect();
+    let index_of: HashMap<&str, usize> = stage_systems
+        .iter()
+        .enumerate()
+        .map(|(index, system)| (system.name.as_str(), index))
+        .collect();
+
+    // dependents[i] run after system i
+    let mut dependents: Vec<Vec<usize>> = vec![vec![]; stage_systems.len()];
+    let mut dependency_count = vec![0; stage_systems.len()];
+    let mut edges = HashSet::new();
+    for (index, system) in stage_systems.iter().enumerate() {
+        let constraints = system
+            .before
+            .iter()
+            .map(|name| (name, true))
+            .chain(system.after.iter().map(|name| (name, false)));
+        for (name, is_before) in constraints {
+            let Some(&other) = index_of.get(name.as_str()) else {
+                log!(format!(
+                    "System {} is ordered against {}, which is not in stage {:?}",
+                    system.name, name, stage
+                ));
+                continue;
+            };
+            let (first, second) = if is_before {
+                (index, other)
+            } else {
+                (other, index)
+            };
+            dependents[first].push(second);
+            dependency_count[second] += 1;
+            edges.insert((
+                stage_systems[first].name.clone(),
+                stage_systems[second].name.clone(),
+            ));
+        }
+    }
+
+    let mut order = vec![];
+    let mut is_placed = vec![false; stage_systems.len()];
+    while order.len() < stage_systems.len() {
+        let next = (0..stage_systems.len())
+            .find(|index| !is_placed[*index] && dependency_count[*index] == 0)
+            .unwrap_or_else(|| {
+                let cycle: Vec<&str> = (0..stage_systems.len())
+                    .filter(|index| !is_placed[*index])
+                    .map(|index| stage_systems[index].name.as_str())
+                    .collect();
+                panic!("systems in stage {:?} form a cycle: {:?}", stage, cycle)
+            });
+        is_placed[next] = true;
+        for dependent in dependents[next].iter() {
+            dependency_count[*dependent] -= 1;
+        }
+        order.push(stage_systems[next].name.clone());
+    }
+    (order, edges)
+}
@@ -0,0 +1,678 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+use crate::core::ecs::{
+    entities::entity::Entity,
+    query::{
+        ComponentAccess, Query, QueryColumns, QueryData, QueryFilter, ReadOnlyQueryData,
+        check_access,
+    },
+};
+
+// When a component was inserted and last borrowed mutably or marked as
+// changed, in ticks of
+// `ComponentRegistry::get_change_tick`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: u64,
+    pub changed: u64,
+}
+
+impl ComponentTicks {
+    fn new(tick: u64) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    pub fn is_added(&self, since: u64) -> bool {
+        self.added > since
+    }
+
+    pub fn is_changed(&self, since: u64) -> bool {
+        self.changed > since
+    }
+}
+
+// Components of one type packed in a dense array, found in O(1) through the
+// entity index. Removal swaps the last component into the hole.
+pub struct SparseSet<T> {
+    // Entity index to position in `dense` and `data`
+    sparse: Vec<Option<u32>>,
+    dense: Vec<Entity>,
+    data: Vec<T>,
+    ticks: Vec<ComponentTicks>,
+    // Stamped on every mutable access, set by the registry
+    change_tick: u64,
+}
+
+// Not derived, which would require `T: Default`
+impl<T> Default for SparseSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SparseSet<T> {
+    pub fn new() -> Self {
+        Self {
+            sparse: vec![],
+            dense: vec![],
+            data: vec![],
+            ticks: vec![],
+            change_tick: 0,
+        }
+    }
+
+    pub(crate) fn set_change_tick(&mut self, tick: u64) {
+        self.change_tick = tick;
+    }
+
+    fn get_dense_index(&self, entity: &Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.get_index() as usize)?)? as usize;
+        (self.dense[index] == *entity).then_some(index)
+    }
+
+    // Returns the component the entity had before, if any
+    pub fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        let slot = entity.get_index() as usize;
+        if slot >= self.sparse.len() {
+            self.sparse.resize(slot + 1, None);
+        }
+        match self.sparse[slot] {
+            Some(index) => {
+                // A stale occupant of the slot is replaced as well
+                let index = index as usize;
+                let previous = std::mem::replace(&mut self.data[index], component);
+                let is_same = std::mem::replace(&mut self.dense[index], entity) == entity;
+                if is_same {
+                    self.ticks[index].changed = self.change_tick;
+                } else {
+                    self.ticks[index] = ComponentTicks::new(self.change_tick);
+                }
+                is_same.then_some(previous)
+            }
+            None => {
+                self.sparse[slot] = Some(self.dense.len() as u32);
+                self.dense.push(entity);
+                self.data.push(component);
+                self.ticks.push(ComponentTicks::new(self.change_tick));
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, entity: &Entity) -> Option<&T> {
+        Some(&self.data[self.get_dense_index(entity)?])
+    }
+
+    // Counts as a change, whether or not the component is written
+    pub fn get_mut(&mut self, entity: &Entity) -> Option<&mut T> {
+        let index = self.get_dense_index(entity)?;
+        self.ticks[index].changed = self.change_tick;
+        Some(&mut self.data[index])
+    }
+
+    // For engine passes that write derived data, see `par_iter_mut_untracked`
+    pub(crate) fn get_mut_untracked(&mut self, entity: &Entity) -> Option<&mut T> {
+        let index = self.get_dense_index(entity)?;
+        Some(&mut self.data[index])
+    }
+
+    pub fn mark_changed(&mut self, entity: &Entity) -> bool {
+        match self.get_dense_index(entity) {
+            Some(index) => {
+                self.ticks[index].changed = self.change_tick;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_ticks(&self, entity: &Entity) -> Option<&ComponentTicks> {
+        Some(&self.ticks[self.get_dense_index(entity)?])
+    }
+
+    pub fn remove(&mut self, entity: &Entity) -> Option<T> {
+        let index = self.get_dense_index(entity)?;
+        self.sparse[entity.get_index() as usize] = None;
+        self.dense.swap_remove(index);
+        if let Some(moved) = self.dense.get(index) {
+            self.sparse[moved.get_index() as usize] = Some(index as u32);
+        }
+        self.ticks.swap_remove(index);
+        Some(self.data.swap_remove(index))
+    }
+
+    pub fn contains(&self, entity: &Entity) -> bool {
+        self.get_dense_index(entity).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+
+    pub fn get_entities(&self) -> &[Entity] {
+        &self.dense
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Entity, &T)> {
+        self.dense.iter().zip(self.data.iter())
+    }
+
+    // Marks every component it reaches as changed
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&Entity, &mut T)> {
+        let tick = self.change_tick;
+        self.dense
+            .iter()
+            .zip(self.data.iter_mut().zip(self.ticks.iter_mut()))
+            .map(move |(entity, (component, ticks))| {
+                ticks.changed = tick;
+                (entity, component)
+            })
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl IndexedParallelIterator<Item = (&Entity, &mut T)>
+    where
+        T: Send,
+    {
+        let tick = self.change_tick;
+        self.dense
+            .par_iter()
+            .zip(self.data.par_iter_mut().zip(self.ticks.par_iter_mut()))
+            .map(move |(entity, (component, ticks))| {
+                ticks.changed = tick;
+                (entity, component)
+            })
+    }
+
+    // For engine passes that write derived data, e.g. cached matrices, which
+    // must not look like a change to the next pass
+    pub(crate) fn par_iter_mut_untracked(
+        &mut self,
+    ) -> impl IndexedParallelIterator<Item = (&Entity, &mut T, &ComponentTicks)>
+    where
+        T: Send,
+    {
+        self.dense
+            .par_iter()
+            .zip(self.data.par_iter_mut().zip(self.ticks.par_iter()))
+            .map(|(entity, (component, ticks))| (entity, component, ticks))
+    }
+
+    // Lends every component out once, for queries borrowing them mutably
+    pub(crate) fn split_slots(&mut self) -> SparseSlots<'_, T> {
+        SparseSlots {
+            sparse: &self.sparse,
+            dense: &self.dense,
+            slots: self.data.iter_mut().map(Some).collect(),
+            ticks: &mut self.ticks,
+            change_tick: self.change_tick,
+        }
+    }
+}
+
+pub struct SparseSlots<'w, T> {
+    sparse: &'w [Option<u32>],
+    dense: &'w [Entity],
+    slots: Vec<Option<&'w mut T>>,
+    ticks: &'w mut [ComponentTicks],
+    change_tick: u64,
+}
+
+impl<'w, T> SparseSlots<'w, T> {
+    pub(crate) fn empty() -> Self {
+        Self {
+            sparse: &[],
+            dense: &[],
+            slots: vec![],
+            ticks: &mut [],
+            change_tick: 0,
+        }
+    }
+
+    // Each component can be taken once, and counts as changed when it is
+    pub(crate) fn take(&mut self, entity: &Entity) -> Option<&'w mut T> {
+        let index = (*self.sparse.get(entity.get_index() as usize)?)? as usize;
+        if self.dense[index] != *entity {
+            return None;
+        }
+        self.ticks[index].changed = self.change_tick;
+        self.slots[index].take()
+    }
+}
+
+// Type-erased storage for all components of one type
+pub trait ComponentColumn: Any {
+    // Returns false when the entity had no component in this column
+    fn remove_entity(&mut self, entity: &Entity) -> bool;
+    fn contains_entity(&self, entity: &Entity) -> bool;
+    fn set_change_tick(&mut self, tick: u64);
+    fn entity_ids(&self) -> Vec<Entity>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> ComponentColumn for SparseSet<T> {
+    fn remove_entity(&mut self, entity: &Entity) -> bool {
+        self.remove(entity).is_some()
+    }
+
+    fn contains_entity(&self, entity: &Entity) -> bool {
+        self.contains(entity)
+    }
+
+    fn set_change_tick(&mut self, tick: u64) {
+        SparseSet::set_change_tick(self, tick);
+    }
+
+    fn entity_ids(&self) -> Vec<Entity> {
+        self.dense.clone()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// One column per component type, keyed by the component's `TypeId`
+pub struct ComponentRegistry {
+    columns: HashMap<TypeId, Box<dyn ComponentColumn>>,
+    // Stamped on inserted and mutably borrowed components. The scene moves it
+    // forward around every system run.
+    change_tick: u64,
+    // What `Added`/`Changed` filters and `get_removed` compare against: the
+    // running system's previous run, see `Scene::run_stage`
+    last_run_tick: u64,
+    // Removed components per type, with the tick of the removal
+    removed: HashMap<TypeId, Vec<(Entity, u64)>>,
+}
+
+// Not derived, ticks start at 1 so everything inserted counts as added
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        Self {
+            columns: HashMap::new(),
+            change_tick: 1,
+            last_run_tick: 0,
+            removed: HashMap::new(),
+        }
+    }
+
+    pub fn get_change_tick(&self) -> u64 {
+        self.change_tick
+    }
+
+    // Returns the new tick
+    pub(crate) fn advance_change_tick(&mut self) -> u64 {
+        self.change_tick += 1;
+        self.change_tick
+    }
+
+    pub fn get_last_run_tick(&self) -> u64 {
+        self.last_run_tick
+    }
+
+    pub(crate) fn set_last_run_tick(&mut self, tick: u64) {
+        self.last_run_tick = tick;
+    }
+
+    // Returns the component the entity had before, if any
+    pub fn insert<T: 'static>(&mut self, id: Entity, component: T) -> Option<T> {
+        let column = self
+            .columns
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+            .expect("component column has the wrong type");
+        column.set_change_tick(self.change_tick);
+        column.insert(id, component)
+    }
+
+    pub fn get<T: 'static>(&self, id: &Entity) -> Option<&T> {
+        self.get_column::<T>()?.get(id)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, id: &Entity) -> Option<&mut T> {
+        self.get_column_mut::<T>()?.get_mut(id)
+    }
+
+    pub(crate) fn get_mut_untracked<T: 'static>(&mut self, id: &Entity) -> Option<&mut T> {
+        self.get_column_mut::<T>()?.get_mut_untracked(id)
+    }
+
+    // Counts as a change without borrowing the component
+    pub fn mark_changed<T: 'static>(&mut self, id: &Entity) -> bool {
+        self.get_column_mut::<T>()
+            .is_some_and(|column| column.mark_changed(id))
+    }
+
+    pub fn remove<T: 'static>(&mut self, id: &Entity) -> Option<T> {
+        let removed = self.get_column_mut::<T>()?.remove(id)?;
+        self.record_removal(TypeId::of::<T>(), *id);
+        Some(removed)
+    }
+
+    pub fn has<T: 'static>(&self, id: &Entity) -> bool {
+        self.get_column::<T>()
+            .is_some_and(|column| column.contains(id))
+    }
+
+    pub fn get_ticks<T: 'static>(&self, id: &Entity) -> Option<&ComponentTicks> {
+        self.get_column::<T>()?.get_ticks(id)
+    }
+
+    // Inserted since the last run of the running system
+    pub fn is_added<T: 'static>(&self, id: &Entity) -> bool {
+        self.get_ticks::<T>(id)
+            .is_some_and(|ticks| ticks.is_added(self.last_run_tick))
+    }
+
+    // Inserted or borrowed mutably since the last run of the running system
+    pub fn is_changed<T: 'static>(&self, id: &Entity) -> bool {
+        self.get_ticks::<T>(id)
+            .is_some_and(|ticks| ticks.is_changed(self.last_run_tick))
+    }
+
+    // Entities that lost the component since the last run of the running
+    // system, including despawned ones
+    pub fn get_removed<T: 'static>(&self) -> Vec<Entity> {
+        self.removed
+            .get(&TypeId::of::<T>())
+            .map_or(vec![], |removed| {
+                removed
+                    .iter()
+                    .filter(|(_, tick)| *tick > self.last_run_tick)
+                    .map(|(id, _)| *id)
+                    .collect()
+            })
+    }
+
+    // Forgets removals older than the tick
+    pub(crate) fn clear_removed_before(&mut self, tick: u64) {
+        for removed in self.removed.values_mut() {
+            removed.retain(|(_, removed_tick)| *removed_tick >= tick);
+        }
+    }
+
+    fn record_removal(&mut self, type_id: TypeId, id: Entity) {
+        self.removed
+            .entry(type_id)
+            .or_default()
+            .push((id, self.change_tick));
+    }
+
+    pub fn get_component_types(&self, id: &Entity) -> Vec<TypeId> {
+        self.columns
+            .iter()
+            .filter(|(_, column)| column.contains_entity(id))
+            .map(|(type_id, _)| *type_id)
+            .collect()
+    }
+
+    // Removes every component of the entity, whatever its type
+    pub fn remove_entity(&mut self, id: &Entity) {
+        let mut removed_types = vec![];
+        for (type_id, column) in self.columns.iter_mut() {
+            if column.remove_entity(id) {
+                removed_types.push(*type_id);
+            }
+        }
+        for type_id in removed_types {
+            self.record_removal(type_id, *id);
+        }
+    }
+
+    pub fn get_column<T: 'static>(&self) -> Option<&SparseSet<T>> {
+        self.columns
+            .get(&TypeId::of::<T>())
+            .and_then(|column| column.as_any().downcast_ref::<SparseSet<T>>())
+    }
+
+    pub fn get_column_mut<T: 'static>(&mut self) -> Option<&mut SparseSet<T>> {
+        let column = self
+            .columns
+            .get_mut(&TypeId::of::<T>())?
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()?;
+        column.set_change_tick(self.change_tick);
+        Some(column)
+    }
+
+    pub fn iter<T: 'static>(&self) -> impl Iterator<Item = (&Entity, &T)> {
+        self.get_column::<T>()
+            .into_iter()
+            .flat_map(|column| column.iter())
+    }
+
+    pub fn iter_mut<T: 'static>(&mut self) -> impl Iterator<Item = (&Entity, &mut T)> {
+        self.get_column_mut::<T>()
+            .into_iter()
+            .flat_map(|column| column.iter_mut())
+    }
+
+    pub fn query<D: QueryData>(&mut self) -> Query<'_, D> {
+        self.query_filtered::<D, ()>()
+    }
+
+    // Panics if the query borrows the same component mutably twice
+    pub fn query_filtered<D: QueryData, F: QueryFilter>(&mut self) -> Query<'_, D> {
+        let access = Self::get_access::<D>();
+        let ids = self.get_matching_ids::<D, F>(&access);
+
+        let mut columns = QueryColumns::new();
+        for (type_id, column) in self.columns.iter_mut() {
+            let accesses: Vec<&ComponentAccess> = access
+                .iter()
+                .filter(|each| each.type_id == *type_id)
+                .collect();
+            if accesses.iter().any(|each| each.mutable) {
+                column.set_change_tick(self.change_tick);
+                columns.add_exclusive(*type_id, &mut **column);
+            } else if !accesses.is_empty() {
+                columns.add_shared(*type_id, &**column);
+            }
+        }
+
+        Query::new(ids, D::init_state(&mut columns))
+    }
+
+    pub fn query_ref<D: ReadOnlyQueryData>(&self) -> Query<'_, D> {
+        self.query_ref_filtered::<D, ()>()
+    }
+
+    pub fn query_ref_filtered<D: ReadOnlyQueryData, F: QueryFilter>(&self) -> Query<'_, D> {
+        let access = Self::get_access::<D>();
+        let ids = self.get_matching_ids::<D, F>(&access);
+
+        let mut columns = QueryColumns::new();
+        for (type_id, column) in self.columns.iter() {
+            if access.iter().any(|each| each.type_id == *type_id) {
+                columns.add_shared(*type_id, &**column);
+            }
+        }
+
+        Query::new(ids, D::init_state(&mut columns))
+    }
+
+    pub fn get_access<D: QueryData>() -> Vec<ComponentAccess> {
+        let mut access = vec![];
+        D::register_access(&mut access);
+        check_access(&access);
+        access
+    }
+
+    // Lends every column to the systems of one parallel batch. A written
+    // column goes to its only writer, a read one is shared by all readers;
+    // the batch must not both read and write the same component.
+    pub(crate) fn split_columns(
+        &mut self,
+        accesses: &[&[ComponentAccess]],
+    ) -> Vec<QueryColumns<'_>> {
+        let mut split: Vec<QueryColumns> = accesses.iter().map(|_| QueryColumns::new()).collect();
+        for (type_id, column) in self.columns.iter_mut() {
+            let writer = accesses.iter().position(|access| {
+                access
+                    .iter()
+                    .any(|each| each.type_id == *type_id && each.mutable)
+            });
+            if let Some(writer) = writer {
+                column.set_change_tick(self.change_tick);
+                split[writer].add_exclusive(*type_id, &mut **column);
+                continue;
+            }
+
+            let column: &dyn ComponentColumn = &**column;
+            for (index, access) in accesses.iter().enumerate() {
+                if access.iter().any(|each| each.type_id == *type_id) {
+                    split[index].add_shared(*type_id, column);
+                }
+            }
+        }
+        split
+    }
+
+    // Candidates come from the first required component's column, or from
+    // every column when all components are optional
+    pub(crate) fn get_matching_ids<D: QueryData, F: QueryFilter>(
+        &self,
+        access: &[ComponentAccess],
+    ) -> Vec<Entity> {
+        let candidates = match access.iter().find(|each| !each.optional) {
+            Some(required) => match self.columns.get(&required.type_id) {
+                Some(column) => column.entity_ids(),
+                None => vec![],
+            },
+            None => {
+                let mut ids = vec![];
+                for column in self.columns.values() {
+                    ids.extend(column.entity_ids());
+                }
+                ids.sort_unstable();
+                ids.dedup();
+                ids
+            }
+        };
+
+        candidates
+            .into_iter()
+            .filter(|id| D::matches(self, id) && F::matches(self, id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ecs::entities::entity::EntityAllocator;
+
+    #[test]
+    fn stale_entities_do_not_reach_the_new_occupant() {
+        let mut allocator = EntityAllocator::new();
+        let mut set = SparseSet::new();
+        let old = allocator.allocate();
+        set.insert(old, "old");
+        allocator.free(old);
+        let new = allocator.allocate();
+        assert_eq!(new.get_index(), old.get_index());
+
+        // The old component is still there until the slot is taken again
+        assert!(!set.contains(&new));
+        assert_eq!(set.insert(new, "new"), None);
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.get(&new), Some(&"new"));
+        assert_eq!(set.get(&old), None);
+        assert_eq!(set.get_mut(&old), None);
+        assert_eq!(set.remove(&old), None);
+        assert_eq!(set.remove(&new), Some("new"));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn replacing_a_stale_occupant_counts_as_added() {
+        let mut allocator = EntityAllocator::new();
+        let mut set = SparseSet::new();
+        let old = allocator.allocate();
+        set.set_change_tick(1);
+        set.insert(old, 1);
+        allocator.free(old);
+        let new = allocator.allocate();
+
+        set.set_change_tick(5);
+        set.insert(new, 2);
+        let ticks = set.get_ticks(&new).unwrap();
+        assert_eq!(ticks.added, 5);
+        assert_eq!(ticks.changed, 5);
+
+        // Inserting over the same entity only changes it
+        set.set_change_tick(8);
+        assert_eq!(set.insert(new, 3), Some(2));
+        let ticks = set.get_ticks(&new).unwrap();
+        assert_eq!(ticks.added, 5);
+        assert_eq!(ticks.changed, 8);
+    }
+
+    #[test]
+    fn removal_keeps_the_moved_component_reachable() {
+        let mut allocator = EntityAllocator::new();
+        let mut set = SparseSet::new();
+        let entities: Vec<Entity> = (0..3).map(|_| allocator.allocate()).collect();
+        for (value, entity) in entities.iter().enumerate() {
+            set.insert(*entity, value);
+        }
+
+        assert_eq!(set.remove(&entities[0]), Some(0));
+        assert_eq!(set.get(&entities[2]), Some(&2));
+        assert_eq!(set.get(&entities[1]), Some(&1));
+        assert_eq!(set.get_entities().len(), 2);
+    }
+
+    #[test]
+    fn registry_tracks_mutable_access_and_removals() {
+        let mut allocator = EntityAllocator::new();
+        let mut registry = ComponentRegistry::new();
+        let entity = allocator.allocate();
+        registry.insert(entity, 1u32);
+        assert!(registry.is_added::<u32>(&entity));
+
+        registry.set_last_run_tick(registry.get_change_tick());
+        let tick = registry.advance_change_tick();
+        assert!(!registry.is_changed::<u32>(&entity));
+        registry.get::<u32>(&entity);
+        assert!(!registry.is_changed::<u32>(&entity));
+        *registry.get_mut_untracked::<u32>(&entity).unwrap() = 2;
+        assert!(!registry.is_changed::<u32>(&entity));
+        registry.get_mut::<u32>(&entity);
+        assert!(registry.is_changed::<u32>(&entity));
+        assert!(!registry.is_added::<u32>(&entity));
+
+        registry.set_last_run_tick(registry.get_change_tick());
+        registry.advance_change_tick();
+        assert!(registry.mark_changed::<u32>(&entity));
+        assert!(registry.is_changed::<u32>(&entity));
+        assert!(!registry.mark_changed::<u64>(&entity));
+
+        registry.remove_entity(&entity);
+        assert_eq!(registry.get_removed::<u32>(), vec![entity]);
+        registry.clear_removed_before(tick + 2);
+        assert!(registry.get_removed::<u32>().is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 
-use nalgebra::{Rotation3, Vector3};
+use nalgebra::{UnitQuaternion, Vector3};
 use winit::{
     event::ElementState,
     keyboard::{KeyCode, PhysicalKey},
@@ -8,12 +8,15 @@ use winit::{
 
 use crate::{
     core::ecs::{
-        components::Camera3D,
-        types::{CursorMovedEvent, KeyboardInputEvent},
+        components::{Camera3D, Transform3D},
+        types::{KeyboardInputEvent, MouseMotionEvent, MouseWheelEvent},
     },
     log,
 };
 
+// Just short of straight up/down, where yaw would flip
+pub(crate) const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+
 pub fn camera_3d_handle_ki_event(camera: &mut Camera3D, event: &KeyboardInputEvent) {
     match event.key {
         PhysicalKey::Code(key_code) => {
@@ -35,33 +38,57 @@ pub fn camera_3d_handle_ki_event(camera: &mut Camera3D, event: &KeyboardInputEve
     }
 }
 
-pub fn camera_3d_handle_cm_event(camera: &mut Camera3D, event: &CursorMovedEvent) {
-    camera.cm_events.push(event.clone());
+pub fn camera_3d_handle_mm_event(camera: &mut Camera3D, event: &MouseMotionEvent) {
+    camera.mm_events.push(event.clone());
+}
+
+pub fn camera_3d_handle_mw_event(camera: &mut Camera3D, event: &MouseWheelEvent) {
+    camera.mw_events.push(event.clone());
+}
+
+// Replays the queued key events into the keys still held, and queues those
+// alone again so they keep counting on the next frames
+pub fn camera_3d_collapse_ki_events(camera: &mut Camera3D) -> HashSet<KeyCode> {
+    let mut pressed: HashSet<KeyCode> = HashSet::new();
+    for event in camera.ki_events.iter() {
+        if let PhysicalKey::Code(key) = event.key {
+            match event.state {
+                ElementState::Pressed => {
+                    pressed.insert(key);
+                }
+                ElementState::Released => {
+                    pressed.remove(&key);
+                }
+            }
+        }
+    }
+
+    camera.ki_events.clear();
+    for key in pressed.iter() {
+        camera.ki_events.push(KeyboardInputEvent {
+            key: PhysicalKey::Code(*key),
+            state: ElementState::Pressed,
+            repeat: true,
+        });
+    }
+    pressed
 }
 
-pub fn camera_3d_compute_transform(camera: &mut Camera3D, dt: f32) {
+// Flies the camera's transform, relative to its parent like any transform
+pub fn camera_3d_compute_transform(camera: &mut Camera3D, transform: &mut Transform3D, dt: f32) {
     log!("Computing camera 3d transform");
 
+    // Mouse right turns right and mouse down looks down, unless Y is inverted.
+    // Raw deltas are distances, so they are not scaled by dt.
     let mut d_rot = Vector3::<f32>::new(0.0, 0.0, 0.0);
-
-    let mut last_x = 0.0;
-    let mut last_y = 0.0;
-    let mut is_first = true;
-    for event in camera.cm_events.iter() {
-        if !is_first {
-            d_rot.y += (event.x - last_x) as f32;
-            d_rot.x += (event.y - last_y) as f32;
-        }
-        last_x = event.x;
-        last_y = event.y;
-        is_first = false;
+    let y_sign = if camera.invert_y { 1.0 } else { -1.0 };
+    for event in camera.mm_events.iter() {
+        d_rot.y -= event.delta_x as f32 * camera.sensitivity;
+        d_rot.x += y_sign * event.delta_y as f32 * camera.sensitivity;
     }
 
     // Directions
-    let ea = camera.transform.rotation;
-    let rotation = Rotation3::from_euler_angles(ea.x, ea.y, ea.z);
-    let fv = Vector3::<f32>::new(0.0, 0.0, -1.0);
-    let fv = (rotation * fv).normalize();
+    let fv = transform.get_forward().normalize();
     let uv = Vector3::y();
     let rv = fv.cross(&uv).normalize();
 
@@ -76,39 +103,22 @@ pub fn camera_3d_compute_transform(camera: &mut Camera3D, dt: f32) {
         (KeyCode::AltLeft, -uv),
     ];
 
-    let mut pressed: HashSet<KeyCode> = HashSet::new();
-
-    for event in camera.ki_events.iter() {
-        if let PhysicalKey::Code(key) = event.key {
-            match event.state {
-                ElementState::Pressed => {
-                    pressed.insert(key);
-                }
-                ElementState::Released => {
-                    pressed.remove(&key);
-                }
-            }
-        }
-    }
-
+    let pressed = camera_3d_collapse_ki_events(camera);
     for (key, dir) in key_dir {
         if pressed.contains(&key) {
             d_pos += dir;
         }
     }
+    camera.mm_events.clear();
+    camera.mw_events.clear();
 
-    // Clear events and add those still not released
-    camera.cm_events.clear();
-    camera.ki_events.clear();
-    for key in pressed {
-        camera.ki_events.push(KeyboardInputEvent {
-            key: PhysicalKey::Code(key),
-            state: ElementState::Pressed,
-            repeat: true,
-        });
-    }
+    transform.position += d_pos * dt * camera.speed;
 
-    camera.transform.position += d_pos * dt * camera.speed;
-    camera.transform.rotation += d_rot * dt * camera.rotation_speed;
-    camera.transform.dirty = true;
+    // Yaw turns around the world up and pitch around the camera's own right
+    // axis, which keeps the horizon level
+    let pitch = fv.y.clamp(-1.0, 1.0).asin();
+    let d_pitch = (pitch + d_rot.x).clamp(-MAX_PITCH, MAX_PITCH) - pitch;
+    transform.rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), d_rot.y)
+        * transform.rotation
+        * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), d_pitch);
 }
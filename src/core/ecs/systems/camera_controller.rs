@@ -0,0 +1,76 @@
+use nalgebra::{Matrix4, Point3, UnitQuaternion, Vector2, Vector3};
+
+use crate::core::ecs::{
+    components::{
+        Camera3D, Transform3D,
+        camera_controller::{FollowController, OrbitController, PathController},
+    },
+    systems::{MAX_PITCH, camera_3d_collapse_ki_events, look_at_transform_3d},
+};
+
+// Springs are stepped at least this often, long frames would overshoot
+const MAX_SPRING_STEP: f32 = 1.0 / 120.0;
+
+// Takes the mouse-look as (pitch, yaw) radians and the wheel steps queued on
+// the camera, with the same sensitivity and direction as flying. Held keys
+// stay queued for when the camera flies again.
+pub fn camera_3d_take_look_input(camera: &mut Camera3D) -> (Vector2<f32>, f32) {
+    let mut look = Vector2::zeros();
+    let y_sign = if camera.invert_y { 1.0 } else { -1.0 };
+    for event in camera.mm_events.iter() {
+        look.x += y_sign * event.delta_y as f32 * camera.sensitivity;
+        look.y -= event.delta_x as f32 * camera.sensitivity;
+    }
+    let zoom = camera.mw_events.iter().map(|event| event.delta_y).sum();
+    camera.mm_events.clear();
+    camera.mw_events.clear();
+    camera_3d_collapse_ki_events(camera);
+    (look, zoom)
+}
+
+pub fn camera_3d_orbit(
+    orbit: &mut OrbitController,
+    transform: &mut Transform3D,
+    target: Vector3<f32>,
+    look: Vector2<f32>,
+    zoom: f32,
+) {
+    orbit.pitch = (orbit.pitch + look.x).clamp(-MAX_PITCH, MAX_PITCH);
+    orbit.yaw += look.y;
+    // Each step covers the same part of the distance left
+    orbit.distance = (orbit.distance * (1.0 - orbit.zoom_speed).powf(zoom))
+        .clamp(orbit.min_distance, orbit.max_distance);
+
+    let rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), orbit.yaw)
+        * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), orbit.pitch);
+    transform.position = target + rotation * Vector3::new(0.0, 0.0, orbit.distance);
+    transform.rotation = rotation;
+}
+
+// target_world is the target's world transform this frame
+pub fn camera_3d_follow(
+    follow: &mut FollowController,
+    transform: &mut Transform3D,
+    target_world: &Matrix4<f32>,
+    dt: f32,
+) {
+    let goal = target_world.transform_point(&Point3::from(follow.offset));
+    let look_at = target_world.transform_point(&Point3::from(follow.look_offset));
+
+    let steps = (dt / MAX_SPRING_STEP).ceil().max(1.0);
+    let step = dt / steps;
+    for _ in 0..steps as usize {
+        let acceleration = (goal.coords - transform.position) * follow.stiffness
+            - follow.velocity * follow.damping;
+        follow.velocity += acceleration * step;
+        transform.position += follow.velocity * step;
+    }
+    look_at_transform_3d(transform, look_at.coords, Vector3::y());
+}
+
+pub fn camera_3d_path(path: &mut PathController, transform: &mut Transform3D, dt: f32) {
+    path.advance(dt);
+    let (position, rotation) = path.get_pose(path.time);
+    transform.position = position;
+    transform.rotation = rotation;
+}
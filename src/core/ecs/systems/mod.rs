@@ -1,9 +1,15 @@
 pub mod camera_3d;
+pub mod camera_controller;
 pub mod point_light_3d;
-pub mod structure_3d;
 pub mod transform_3d;
 
 pub use camera_3d::*;
+pub use camera_controller::*;
 pub use point_light_3d::*;
-pub use structure_3d::*;
 pub use transform_3d::*;
+
+// Names of the systems every scene registers, for ordering against them
+pub const CAMERA_3D_SYSTEM: &str = "camera_3d";
+pub const TRANSFORM_3D_SYSTEM: &str = "transform_3d";
+pub const GLOBAL_UNIFORM_SYSTEM: &str = "global_uniform";
+pub const LIGHT_UNIFORMS_SYSTEM: &str = "light_uniforms";
@@ -1,46 +1,112 @@
-use nalgebra::{Matrix4, Rotation3, Translation3, Unit, Vector3};
+use nalgebra::{Matrix3, Matrix4, Translation3, Unit, UnitQuaternion, Vector3};
 
 use crate::core::ecs::components::Transform3D;
 
 pub fn translate_transform_3d(t: &mut Transform3D, delta: Vector3<f32>) {
     t.position += delta;
-    t.dirty = true;
 }
 
-// delta contains (roll, pitch, yaw)
+// delta contains (roll, pitch, yaw), added to the current Euler angles
 pub fn rotate_transform_3d(t: &mut Transform3D, delta: Vector3<f32>) {
-    t.rotation += delta;
-    t.dirty = true;
+    let (roll, pitch, yaw) = t.rotation.euler_angles();
+    t.rotation = UnitQuaternion::from_euler_angles(roll + delta.x, pitch + delta.y, yaw + delta.z);
+}
+
+// delta contains (roll, pitch, yaw), applied around the transform's own axes
+pub fn rotate_transform_3d_local(t: &mut Transform3D, delta: Vector3<f32>) {
+    t.rotation *= UnitQuaternion::from_euler_angles(delta.x, delta.y, delta.z);
+}
+
+// Rotates in place around an axis given in parent space
+pub fn rotate_transform_3d_around_axis(t: &mut Transform3D, axis: Vector3<f32>, angle: f32) {
+    if angle == 0.0 || axis == Vector3::zeros() {
+        return;
+    }
+
+    let axis_unit: Unit<Vector3<f32>> = Unit::new_normalize(axis);
+    t.rotation = UnitQuaternion::from_axis_angle(&axis_unit, angle) * t.rotation;
+}
+
+// Euler angles as (roll, pitch, yaw), replacing the current rotation
+pub fn set_transform_3d_euler_angles(t: &mut Transform3D, angles: Vector3<f32>) {
+    t.rotation = UnitQuaternion::from_euler_angles(angles.x, angles.y, angles.z);
+}
+
+// Turns forward (-Z) towards the target, keeping up as close to `up` as possible
+pub fn look_at_transform_3d(t: &mut Transform3D, target: Vector3<f32>, up: Vector3<f32>) {
+    let direction = target - t.position;
+    if direction.norm_squared() < f32::EPSILON {
+        return;
+    }
+
+    // Looking straight along `up` leaves the roll undefined
+    let up = if direction.cross(&up).norm_squared() < f32::EPSILON {
+        Vector3::z()
+    } else {
+        up
+    };
+    // `face_towards` points +Z at the direction
+    t.rotation = UnitQuaternion::face_towards(&-direction, &up);
 }
 
 // Multiply scale by given factors
 pub fn scale_transform_3d_mul(t: &mut Transform3D, factors: Vector3<f32>) {
     t.scale.component_mul_assign(&factors);
-    t.dirty = true;
 }
 
 // Set scale to an absolute value
 pub fn scale_transform_3d_set(t: &mut Transform3D, new_scale: Vector3<f32>) {
     t.scale = new_scale;
-    t.dirty = true;
+}
+
+// Blends two states of a transform, e.g. the last two fixed updates.
+// alpha 0 gives `from`, 1 gives `to`.
+pub fn interpolate_transform_3d(from: &Transform3D, to: &Transform3D, alpha: f32) -> Transform3D {
+    Transform3D::new_with_rotation(
+        from.position.lerp(&to.position, alpha),
+        // Opposite rotations have no single shortest path between them
+        from.rotation
+            .try_slerp(&to.rotation, alpha, f32::EPSILON)
+            .unwrap_or(if alpha < 0.5 {
+                from.rotation
+            } else {
+                to.rotation
+            }),
+        from.scale.lerp(&to.scale, alpha),
+    )
+}
+
+// Takes position, rotation and scale from a matrix, e.g. a world transform.
+// Shear, which non-uniform scales under a rotation produce, is dropped.
+pub fn set_transform_3d_from_matrix(t: &mut Transform3D, matrix: &Matrix4<f32>) {
+    let axes: Vec<Vector3<f32>> = (0..3)
+        .map(|i| matrix.fixed_view::<3, 1>(0, i).into_owned())
+        .collect();
+    let scale = Vector3::new(axes[0].norm(), axes[1].norm(), axes[2].norm());
+    let rotation = Matrix3::from_columns(&[
+        axes[0] / scale.x.max(f32::EPSILON),
+        axes[1] / scale.y.max(f32::EPSILON),
+        axes[2] / scale.z.max(f32::EPSILON),
+    ]);
+    t.position = matrix.fixed_view::<3, 1>(0, 3).into_owned();
+    t.rotation = UnitQuaternion::from_matrix(&rotation);
+    t.scale = scale;
 }
 
 pub fn update_transform_3d_matrix(t: &mut Transform3D) {
-    let r = Rotation3::from_euler_angles(t.rotation.x, t.rotation.y, t.rotation.z);
+    let r = t.rotation;
     let s = Matrix4::new_nonuniform_scaling(&t.scale);
     let tr = Translation3::new(t.position.x, t.position.y, t.position.z);
     t.cached_transform = tr.to_homogeneous() * r.to_homogeneous() * s;
-    t.dirty = false;
 }
 
 pub fn update_transforms_3d(transforms: &mut [Transform3D]) {
     for t in transforms.iter_mut() {
-        if t.dirty {
-            update_transform_3d_matrix(t);
-        }
+        update_transform_3d_matrix(t);
     }
 }
 
+// The orientation turns along, so whatever faced the pivot keeps facing it
 pub fn orbit_transform_3d_around_pivot(
     t: &mut Transform3D,
     pivot: Vector3<f32>,
@@ -52,9 +118,9 @@ pub fn orbit_transform_3d_around_pivot(
     }
 
     let axis_unit: Unit<Vector3<f32>> = Unit::new_normalize(axis);
-    let delta_r = Rotation3::from_axis_angle(&axis_unit, angle);
+    let delta_r = UnitQuaternion::from_axis_angle(&axis_unit, angle);
     let relative = t.position - pivot;
     let rotated = delta_r * relative;
     t.position = pivot + rotated;
-    t.dirty = true;
+    t.rotation = delta_r * t.rotation;
 }
@@ -1,6 +1,10 @@
-use winit::{event::ElementState, keyboard::PhysicalKey};
+use serde::{Deserialize, Serialize};
+use winit::{
+    event::{ElementState, MouseButton},
+    keyboard::PhysicalKey,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyboardInputEvent {
     pub key: PhysicalKey,
     pub state: ElementState,
@@ -13,7 +17,7 @@ impl KeyboardInputEvent {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CursorMovedEvent {
     pub x: f64,
     pub y: f64,
@@ -24,3 +28,41 @@ impl CursorMovedEvent {
         Self { x, y }
     }
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseButtonEvent {
+    pub button: MouseButton,
+    pub state: ElementState,
+}
+
+impl MouseButtonEvent {
+    pub fn new(button: MouseButton, state: ElementState) -> Self {
+        Self { button, state }
+    }
+}
+
+// Scroll amount in lines, positive y scrolls up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseWheelEvent {
+    pub delta_x: f32,
+    pub delta_y: f32,
+}
+
+impl MouseWheelEvent {
+    pub fn new(delta_x: f32, delta_y: f32) -> Self {
+        Self { delta_x, delta_y }
+    }
+}
+
+// Raw, unaccelerated mouse movement, reported even while the cursor is grabbed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseMotionEvent {
+    pub delta_x: f64,
+    pub delta_y: f64,
+}
+
+impl MouseMotionEvent {
+    pub fn new(delta_x: f64, delta_y: f64) -> Self {
+        Self { delta_x, delta_y }
+    }
+}
@@ -1,28 +1,55 @@
 use ash::vk;
+use image::RgbaImage;
+use nalgebra::Vector4;
+use std::sync::Arc;
+use std::thread::JoinHandle;
 use std::time::Duration;
 use std::{collections::HashMap, time::Instant};
 use winit::window::Window;
 
+use crate::core::config::EngineConfig;
 use crate::core::ecs::entities::game_object::GameObject;
-use crate::core::ecs::types::{CursorMovedEvent, KeyboardInputEvent};
+use crate::core::ecs::events::EngineEvent;
+use crate::core::ecs::schedule::{System, SystemContext};
+use crate::core::ecs::types::{
+    CursorMovedEvent, KeyboardInputEvent, MouseButtonEvent, MouseMotionEvent, MouseWheelEvent,
+};
 use crate::core::gpu::scene_render::RecordableScene;
+use crate::core::input::recording::{InputRecorder, InputReplay};
+use crate::core::input::{CursorMode, InputMap, InputRecording, InputState, RecordedInputEvent};
+use crate::core::time::FixedTimestep;
 use crate::log;
 use crate::vulkan_backend::backend_event::VBackendEvent;
 use crate::{
     core::{
         ecs::components::{Material3D, Structure3D},
         gpu::{
-            materials_manager::MaterialsManager, scene_render::SceneRenderer, texture::ImageTexture,
+            deletion_queue::DeletionQueue, materials_manager::MaterialsManager, model::Model,
+            scene_render::SceneRenderer, texture::ImageTexture,
         },
+        prefab::{Prefab, PrefabData, PrefabOverrides},
         scene::Scene,
+        scene_file::{EntityData, SceneData},
+        scene_preload::{PreloadedScene, SceneAssets},
+        scene_stack::{OverlayConfig, SceneStack},
         utils::get_random_id,
     },
     shared::types::Id,
     vulkan_backend::{
-        backend::VBackend, descriptor::VDescriptorWriteBatch, frame::context::VFrameRenderContext,
+        backend::VBackend,
+        descriptor::VDescriptorWriteBatch,
+        frame::{VFrameRenderResult, context::VFrameRenderContext},
     },
 };
 
+// Name of the scene set by `GameEngine::set_active_scene`
+pub const DEFAULT_SCENE: &str = "default";
+
+struct FileTexture {
+    texture: Id,
+    material: Material3D,
+}
+
 pub struct GameEngine {
     // Core
     v_backend: VBackend,
@@ -31,18 +58,67 @@ pub struct GameEngine {
 
     // Resources
     textures: HashMap<Id, ImageTexture>,
+    texture_paths: HashMap<Id, String>,
+    // Textures of scene and prefab files by path, loaded once and freed with
+    // their material once no `Material3D` uses it, see `release_unused_assets`
+    file_textures: HashMap<String, FileTexture>,
+    // Every model handed out in a `Structure3D`, freed once nothing else
+    // holds it
+    models: Vec<Arc<Model>>,
+    // Engine resources no longer used, destroyed after the frames in flight
+    deletion_queue: DeletionQueue,
+
+    // Scenes
+    scenes: SceneStack,
+    // Scene files being read on other threads, by the name they'll get
+    preloading: HashMap<String, JoinHandle<PreloadedScene>>,
 
     // State
-    active_scene: Option<Scene>,
+    input: InputState,
+    cursor_mode: CursorMode,
     last_frame_instant: Instant,
     frame_count: usize,
     fps: usize,
     pub frame_time: Duration,
+
+    // Time
+    delta_time: Duration,
+    time_scale: f32,
+    paused: bool,
+    fixed_timestep: Option<FixedTimestep>,
+
+    // Input recording
+    input_recorder: Option<InputRecorder>,
+    input_replay: Option<InputReplay>,
 }
 
 impl GameEngine {
-    pub fn new(window: &Window) -> Self {
-        let v_backend = VBackend::new(window);
+    pub fn new(window: &Window, config: &EngineConfig) -> Self {
+        Self::from_backend(VBackend::new(window, config.get_backend_config()), config)
+    }
+
+    // Renders into offscreen images instead of a window, frames can be read
+    // back with `read_frame`
+    pub fn new_headless(width: u32, height: u32) -> Self {
+        Self::new_headless_with_config(&EngineConfig {
+            width,
+            height,
+            ..EngineConfig::default()
+        })
+    }
+
+    pub fn new_headless_with_config(config: &EngineConfig) -> Self {
+        let image_extent = vk::Extent2D {
+            width: config.width,
+            height: config.height,
+        };
+        Self::from_backend(
+            VBackend::new_headless(image_extent, config.get_headless_backend_config()),
+            config,
+        )
+    }
+
+    fn from_backend(v_backend: VBackend, config: &EngineConfig) -> Self {
         let scene_renderer = SceneRenderer::new(&v_backend);
         let materials_manager = MaterialsManager::new(&v_backend.v_device);
 
@@ -51,11 +127,26 @@ impl GameEngine {
             scene_renderer,
             materials_manager,
             textures: HashMap::new(),
-            active_scene: None,
+            texture_paths: HashMap::new(),
+            file_textures: HashMap::new(),
+            models: vec![],
+            deletion_queue: DeletionQueue::new(),
+            scenes: SceneStack::new(),
+            preloading: HashMap::new(),
+            input: InputState::new(),
+            cursor_mode: CursorMode::Normal,
             last_frame_instant: Instant::now(),
             frame_count: 0,
             fps: 0,
             frame_time: Duration::new(0, 0),
+            delta_time: Duration::new(0, 0),
+            time_scale: 1.0,
+            paused: false,
+            fixed_timestep: config
+                .fixed_update_rate
+                .map(|rate| FixedTimestep::new(rate, config.max_fixed_steps_per_frame)),
+            input_recorder: None,
+            input_replay: None,
         };
 
         engine.init();
@@ -73,6 +164,8 @@ impl GameEngine {
             .allocate_material(&self.v_backend.v_device, sampler_layout);
         let default_material = Material3D {
             manager_index: default_material_index,
+            texture_path: None,
+            users: None,
         };
         let mut batch_writer = VDescriptorWriteBatch::new();
         default_material.queue_descriptor_writes(
@@ -87,64 +180,333 @@ impl GameEngine {
         Scene::new(&self.v_backend, &self.scene_renderer)
     }
 
+    // Builds the scene saved by `Scene::save_to_file`, loading its meshes and
+    // textures. The scene is returned inactive, like `create_scene`.
+    pub fn load_scene(&mut self, path: &str) -> Scene {
+        self.create_scene_from_data(&SceneData::from_file(path))
+    }
+
+    pub fn create_scene_from_data(&mut self, data: &SceneData) -> Scene {
+        self.create_scene_from_assets(data, &SceneAssets::new())
+    }
+
+    // Only uploads the assets read ahead of time, the rest is read from disk
+    pub fn create_scene_from_assets(&mut self, data: &SceneData, assets: &SceneAssets) -> Scene {
+        let mut scene = self.create_scene();
+        scene.set_ambient_color(Vector4::from(data.ambient_color));
+
+        // Loaded like a prefab, so entities with the same mesh share the model
+        let prefab = self.create_prefab_from_entities(&data.entities, assets);
+        let entities = prefab.instantiate(&self.v_backend, &mut scene, &PrefabOverrides::default());
+
+        if let Some(camera) = data.active_camera {
+            let camera = entities
+                .get(camera)
+                .expect("scene file refers to an active camera that does not exist");
+            scene.set_active_camera(camera);
+        }
+        scene
+    }
+
+    pub fn load_prefab(&mut self, path: &str) -> Prefab {
+        self.create_prefab_from_data(&PrefabData::from_file(path))
+    }
+
+    pub fn create_prefab_from_data(&mut self, data: &PrefabData) -> Prefab {
+        assert!(!data.entities.is_empty(), "prefab has no entities");
+        self.create_prefab_from_entities(&data.entities, &SceneAssets::new())
+    }
+
+    fn create_prefab_from_entities(
+        &mut self,
+        entities: &[EntityData],
+        assets: &SceneAssets,
+    ) -> Prefab {
+        let mut prefab = Prefab::new();
+        let mut structures: HashMap<String, Structure3D> = HashMap::new();
+        for entity_data in entities {
+            let structure = entity_data.mesh.as_ref().map(|mesh| {
+                structures
+                    .entry(mesh.clone())
+                    .or_insert_with(|| {
+                        let model = match assets.meshes.get(mesh) {
+                            Some(mesh_data) => Model::from_mesh_data(&self.v_backend, mesh_data),
+                            None => Model::from_obj(&self.v_backend, mesh),
+                        };
+                        self.add_model(model, Some(mesh))
+                    })
+                    .clone()
+            });
+            let material = entity_data
+                .texture
+                .as_ref()
+                .map(|texture| self.get_file_material_3d(texture, assets));
+            prefab.push(entity_data.clone(), structure, material);
+        }
+        prefab
+    }
+
+    // Shared by every scene and prefab using the image, until the last of them
+    // is gone
+    fn get_file_material_3d(&mut self, image_path: &str, assets: &SceneAssets) -> Material3D {
+        if let Some(file_texture) = self.file_textures.get(image_path) {
+            return file_texture.material.clone();
+        }
+        let texture = match assets.images.get(image_path) {
+            Some(image) => self.load_texture_from_rgba(image_path, image),
+            None => self.load_texture_from_image(image_path),
+        };
+        let mut material = self.get_material_3d_from_texture(texture);
+        material.users = Some(Arc::new(()));
+        self.file_textures.insert(
+            image_path.into(),
+            FileTexture {
+                texture,
+                material: material.clone(),
+            },
+        );
+        material
+    }
+
+    // Spawns a copy of the prefab into the active scene and returns its root
+    pub fn instantiate_prefab(
+        &mut self,
+        prefab: &Prefab,
+        overrides: PrefabOverrides,
+    ) -> GameObject {
+        let scene = self.scenes.get_active_mut().expect("No active scene");
+        let entities = prefab.instantiate(&self.v_backend, scene, &overrides);
+        entities[0].clone()
+    }
+
+    // Instances keep working, the models, textures and materials are freed
+    // with the last of them. Dropping the prefab does the same, a frame later.
+    pub fn unload_prefab(&mut self, prefab: Prefab) {
+        drop(prefab);
+        self.release_unused_assets();
+    }
+
+    // The scene at the bottom of the stack, see `switch_to_scene`
     pub fn get_active_scene(&mut self) -> &mut Scene {
-        self.active_scene.as_mut().unwrap()
+        self.scenes.get_active_mut().expect("No active scene")
     }
 
+    // Adds the scene under `DEFAULT_SCENE` and switches to it. The scene set
+    // by the previous call is unloaded.
     pub fn set_active_scene(&mut self, scene: Scene) {
-        self.active_scene = Some(scene);
+        self.add_scene(DEFAULT_SCENE, scene);
+        self.switch_to_scene(DEFAULT_SCENE);
+    }
+
+    // The engine owns the scene from now on, until `unload_scene` or
+    // `destroy`. A scene already loaded under the name is unloaded.
+    pub fn add_scene(&mut self, name: &str, scene: Scene) {
+        if self.scenes.contains(name) {
+            log!(format!("Replacing scene {}", name));
+            self.unload_scene(name);
+        }
+        self.scenes.insert(name, scene);
+    }
+
+    // Waits for the GPU, then destroys the scene and its GPU resources.
+    // Models, textures and materials shared with other scenes or prefabs are
+    // freed with the last user. A preload under the name is dropped.
+    pub fn unload_scene(&mut self, name: &str) -> bool {
+        let was_preloading = self.preloading.remove(name).is_some();
+        let Some(mut scene) = self.scenes.remove(name) else {
+            return was_preloading;
+        };
+        self.v_backend.v_device.wait_till_idle();
+        scene.destroy(&self.v_backend);
+        drop(scene);
+        self.release_unused_assets();
+        true
+    }
+
+    pub fn has_scene(&self, name: &str) -> bool {
+        self.scenes.contains(name)
+    }
+
+    pub fn get_scene(&mut self, name: &str) -> Option<&mut Scene> {
+        self.scenes.get_mut(name)
+    }
+
+    // Makes the named scene the active one and removes every overlay. The
+    // previous scenes stay loaded. Waits for the scene if it is preloading.
+    pub fn switch_to_scene(&mut self, name: &str) {
+        self.finish_preload(name);
+        self.scenes.set_active(name);
+        self.get_active_scene()
+            .send_event(EngineEvent::SceneActivated);
+    }
+
+    // Draws the named scene over the stack, e.g. a pause menu over the game
+    pub fn push_overlay(&mut self, name: &str, overlay: OverlayConfig) {
+        self.finish_preload(name);
+        self.scenes.push_overlay(name, overlay);
+        if let Some(scene) = self.scenes.get_mut(name) {
+            scene.send_event(EngineEvent::SceneActivated);
+        }
+    }
+
+    // The overlay stays loaded
+    pub fn pop_overlay(&mut self) -> Option<String> {
+        self.scenes.pop_overlay()
+    }
+
+    pub fn get_active_scene_name(&self) -> Option<&str> {
+        self.scenes.get_active_name()
+    }
+
+    // Active scene first, then the overlays bottom to top
+    pub fn get_scene_stack(&self) -> Vec<&str> {
+        self.scenes.get_stack_names()
+    }
+
+    // Reads the scene file with its meshes and images on another thread. The
+    // scene is built on the main thread once that is done, at the start of
+    // a frame or when it is needed, and is then loaded under the name.
+    pub fn preload_scene(&mut self, name: &str, path: &str) {
+        let path = path.to_string();
+        let handle = std::thread::spawn(move || PreloadedScene::load(&path));
+        if self.preloading.insert(name.into(), handle).is_some() {
+            log!(format!("Replacing scene preload {}", name));
+        }
+    }
+
+    pub fn is_scene_preloading(&self, name: &str) -> bool {
+        self.preloading.contains_key(name)
+    }
+
+    // Builds the preloaded scenes whose files were read
+    fn finish_preloads(&mut self) {
+        let finished: Vec<String> = self
+            .preloading
+            .iter()
+            .filter(|(_, handle)| handle.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in finished {
+            self.finish_preload(&name);
+        }
+    }
+
+    // Blocks until the scene file was read
+    fn finish_preload(&mut self, name: &str) {
+        let Some(handle) = self.preloading.remove(name) else {
+            return;
+        };
+        let preloaded = handle.join().expect("failed to preload scene");
+        let scene = self.create_scene_from_assets(&preloaded.data, &preloaded.assets);
+        self.add_scene(name, scene);
+    }
+
+    // Sends the event on the bus of the active scene
+    pub fn send_event<T: 'static>(&mut self, event: T) {
+        self.get_active_scene().send_event(event);
+    }
+
+    // Registers the system on the active scene
+    pub fn add_system(&mut self, system: System) {
+        self.get_active_scene().add_system(system);
     }
 
     pub fn enable_shadow_for_spot_light_3d(&mut self, entity: &GameObject) {
-        let scene = self.active_scene.as_mut().expect("No active scene");
+        let scene = self.scenes.get_active_mut().expect("No active scene");
         scene
             .shadow_mapping
             .add_spot_light(&self.v_backend, *entity.get_id());
+    }
 
-        let _shadow_map = scene
-            .shadow_mapping
-            .spot_light_maps
-            .get(entity.get_id())
-            .unwrap();
-        let _shadow_map_view = scene
-            .shadow_mapping
-            .spot_light_views
-            .get(entity.get_id())
-            .unwrap();
+    pub fn disable_shadow_for_spot_light_3d(&mut self, entity: &GameObject) {
+        let scene = self.scenes.get_active_mut().expect("No active scene");
+        scene.queue_spot_light_shadow_removal(entity);
+    }
 
-        // self.scene_render.v_shadow_rendering_system.add_framebuffer(
-        //     &self.v_backend.v_device,
-        //     *entity.get_id(),
-        //     None,
-        //     Some(shadow_map_view),
-        //     shadow_map.config.get_extent_2d(),
-        //     true,
-        //     true,
-        // );
+    pub fn get_structure_3d_from_obj(&mut self, obj_path: &str) -> Structure3D {
+        let model = Model::from_obj(&self.v_backend, obj_path);
+        self.add_model(model, Some(obj_path))
     }
 
-    pub fn disable_shadow_for_spot_light_3d(&mut self, entity: &GameObject) {
-        let scene = self.active_scene.as_mut().expect("No active scene");
-        scene
-            .shadow_mapping
-            .remove_spot_light(&self.v_backend, entity.get_id());
-        // self.scene_render
-        //     .v_shadow_rendering_system
-        //     .remove_framebuffer(&self.v_backend.v_device, entity.get_id());
+    // For models built in code, e.g. with `Model::new`
+    pub fn get_structure_3d_from_model(&mut self, model: Model) -> Structure3D {
+        self.add_model(model, None)
+    }
+
+    fn add_model(&mut self, model: Model, asset_path: Option<&str>) -> Structure3D {
+        let model = Arc::new(model);
+        self.models.push(model.clone());
+        Structure3D::new(model, asset_path.map(String::from))
+    }
+
+    // Models in use, not counting the ones queued for deletion
+    pub fn get_model_count(&self) -> usize {
+        self.models.len()
+    }
+
+    // Textures in use, the default one included
+    pub fn get_texture_count(&self) -> usize {
+        self.textures.len()
     }
 
-    pub fn get_structure_3d_from_obj(&self, obj_path: &str) -> Structure3D {
-        Structure3D::from_obj(&self.v_backend, obj_path)
+    // Queues the models, and the textures and materials of files, that only
+    // the engine still holds for deletion
+    fn release_unused_assets(&mut self) {
+        let (unused, used): (Vec<_>, Vec<_>) = std::mem::take(&mut self.models)
+            .into_iter()
+            .partition(|model| Arc::strong_count(model) == 1);
+        self.models = used;
+        for model in unused {
+            self.deletion_queue
+                .push(move |v_backend| model.destroy(v_backend));
+        }
+
+        let unused: Vec<String> = self
+            .file_textures
+            .iter()
+            .filter(|(_, file_texture)| {
+                file_texture
+                    .material
+                    .users
+                    .as_ref()
+                    .is_some_and(|users| Arc::strong_count(users) == 1)
+            })
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in unused {
+            let file_texture = self.file_textures.remove(&path).unwrap();
+            self.materials_manager.free_material(
+                file_texture.material.manager_index,
+                &mut self.deletion_queue,
+            );
+            self.texture_paths.remove(&file_texture.texture);
+            if let Some(texture) = self.textures.remove(&file_texture.texture) {
+                self.deletion_queue
+                    .push(move |v_backend| texture.destroy(v_backend));
+            }
+        }
     }
 
     pub fn load_texture_from_image(&mut self, image_path: &str) -> Id {
         let texture = ImageTexture::new(&self.v_backend, image_path, vk::Format::R8G8B8A8_SRGB);
+        self.add_texture(texture, image_path)
+    }
+
+    // For images decoded ahead of time, see `preload_scene`
+    fn load_texture_from_rgba(&mut self, image_path: &str, image: &RgbaImage) -> Id {
+        let texture = ImageTexture::from_rgba(&self.v_backend, image, vk::Format::R8G8B8A8_SRGB);
+        self.add_texture(texture, image_path)
+    }
+
+    fn add_texture(&mut self, texture: ImageTexture, image_path: &str) -> Id {
         let id = get_random_id();
         self.textures.insert(id, texture);
+        self.texture_paths.insert(id, image_path.into());
         id
     }
 
     pub fn get_material_3d_from_texture(&mut self, texture: Id) -> Material3D {
+        let texture_path = self.texture_paths.get(&texture).cloned();
         let texture = self
             .textures
             .get(&texture)
@@ -158,6 +520,8 @@ impl GameEngine {
 
         let material = Material3D {
             manager_index: allocated_sets_index,
+            texture_path,
+            users: None,
         };
 
         material.queue_descriptor_writes(&mut self.materials_manager, texture, &mut batch_writer);
@@ -165,52 +529,318 @@ impl GameEngine {
         material
     }
 
+    // Destroyed after the frames in flight. Textures of scene and prefab files
+    // are refused, they are freed with their last user instead
     pub fn unload_texture(&mut self, texture: Id) {
+        assert!(
+            !self
+                .file_textures
+                .values()
+                .any(|file_texture| file_texture.texture == texture),
+            "texture passed to unload_texture belongs to a scene or prefab file"
+        );
         if let Some(tex) = self.textures.remove(&texture) {
-            tex.destroy(&self.v_backend);
+            self.deletion_queue
+                .push(move |v_backend| tex.destroy(v_backend));
         }
+        self.texture_paths.remove(&texture);
     }
 
     pub fn emit_update_framebuffers(&mut self, window: &Window) {
+        if self.v_backend.is_suspended() {
+            return;
+        }
         self.v_backend.recreate_swapchain(window);
+        self.update_framebuffers();
+    }
+
+    // Releases the surface and everything built on it, scenes and their GPU
+    // resources are kept for `resume`
+    pub fn suspend(&mut self) {
+        self.v_backend.suspend();
+        self.update_framebuffers();
+    }
+
+    pub fn resume(&mut self, window: &Window) {
+        self.v_backend.resume(window);
+        self.update_framebuffers();
+        self.reset_frame_clock();
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.v_backend.is_suspended()
+    }
+
+    // Headless counterpart of `emit_update_framebuffers`
+    pub fn resize_headless(&mut self, width: u32, height: u32) {
+        self.v_backend
+            .recreate_offscreen(vk::Extent2D { width, height });
+        self.update_framebuffers();
+    }
+
+    // Restarts frame timing, so the first frame after a pause doesn't get the
+    // whole paused duration as its dt
+    pub fn reset_frame_clock(&mut self) {
+        self.last_frame_instant = Instant::now();
+    }
+
+    // Rebuilds everything that depends on the render target extent
+    fn update_framebuffers(&mut self) {
         let event = VBackendEvent::UpdateFramebuffers(
             &self.v_backend.v_device,
-            &self.v_backend.v_swapchain,
+            &self.v_backend.v_render_target,
         );
         self.scene_renderer.handle_backend_event(&event);
-        if let Some(scene) = &mut self.active_scene {
+        // Inactive scenes too, so they are ready when switched to
+        for scene in self.scenes.iter_mut() {
             scene.handle_backend_event(&event);
+            scene.shadow_mapping.recreate(&self.v_backend);
         }
     }
 
+    pub fn get_input(&self) -> &InputState {
+        &self.input
+    }
+
+    pub fn get_input_mut(&mut self) -> &mut InputState {
+        &mut self.input
+    }
+
+    pub fn load_input_map(&mut self, path: &str) {
+        self.input.input_map = InputMap::from_file(path);
+    }
+
     pub fn handle_keyboard_input(&mut self, event: &KeyboardInputEvent) {
-        if let Some(scene) = &mut self.active_scene {
-            scene.handle_keyboard_input(event);
-        }
+        self.handle_live_input(RecordedInputEvent::KeyboardInput(event.clone()));
     }
 
     pub fn handle_cursor_moved(&mut self, event: &CursorMovedEvent) {
-        if let Some(scene) = &mut self.active_scene {
-            scene.handle_cursor_moved(event);
+        self.handle_live_input(RecordedInputEvent::CursorMoved(event.clone()));
+    }
+
+    // Drives mouse-look on the active camera while the cursor is grabbed
+    pub fn handle_mouse_motion(&mut self, event: &MouseMotionEvent) {
+        self.handle_live_input(RecordedInputEvent::MouseMotion(event.clone()));
+    }
+
+    pub fn get_cursor_mode(&self) -> CursorMode {
+        self.cursor_mode
+    }
+
+    // Applied to the window by the app before the next frame is rendered
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode) {
+        self.handle_live_input(RecordedInputEvent::CursorMode(cursor_mode));
+    }
+
+    pub fn handle_mouse_input(&mut self, event: &MouseButtonEvent) {
+        self.handle_live_input(RecordedInputEvent::MouseButton(event.clone()));
+    }
+
+    // Also zooms orbit cameras
+    pub fn handle_mouse_wheel(&mut self, event: &MouseWheelEvent) {
+        self.handle_live_input(RecordedInputEvent::MouseWheel(event.clone()));
+    }
+
+    // Releases every held key and button, e.g. when the window loses focus
+    pub fn release_all_input(&mut self) {
+        self.handle_live_input(RecordedInputEvent::ReleaseAll);
+    }
+
+    fn handle_live_input(&mut self, event: RecordedInputEvent) {
+        // The replay is the only input source while it runs
+        if self.input_replay.is_some() {
+            return;
         }
+        if let Some(input_recorder) = &mut self.input_recorder {
+            input_recorder.record_event(&event);
+        }
+        self.dispatch_input(&event);
     }
 
-    pub fn pre_render(&mut self) {
-        log!("Game Engine pre render");
+    fn dispatch_input(&mut self, event: &RecordedInputEvent) {
+        match event {
+            RecordedInputEvent::KeyboardInput(event) => {
+                self.input.handle_keyboard_input(event);
+                if let Some(scene) = self.scenes.get_input_scene_mut() {
+                    scene.handle_keyboard_input(event);
+                }
+            }
+            RecordedInputEvent::CursorMoved(event) => self.input.handle_cursor_moved(event),
+            RecordedInputEvent::MouseButton(event) => self.input.handle_mouse_input(event),
+            RecordedInputEvent::MouseWheel(event) => {
+                self.input.handle_mouse_wheel(event);
+                if let Some(scene) = self.scenes.get_input_scene_mut() {
+                    scene.handle_mouse_wheel(event);
+                }
+            }
+            RecordedInputEvent::MouseMotion(event) => {
+                self.input.handle_mouse_motion(event);
+                if self.cursor_mode != CursorMode::Grabbed {
+                    return;
+                }
+                if let Some(scene) = self.scenes.get_input_scene_mut() {
+                    scene.handle_mouse_motion(event);
+                }
+            }
+            RecordedInputEvent::CursorMode(cursor_mode) => self.cursor_mode = *cursor_mode,
+            RecordedInputEvent::ReleaseAll => self.input.release_all(),
+        }
+    }
+
+    // Records every input event and frame time until `stop_input_recording`
+    pub fn start_input_recording(&mut self) {
+        self.input_recorder = Some(InputRecorder::new());
+    }
 
+    pub fn stop_input_recording(&mut self) -> Option<InputRecording> {
+        self.input_recorder.take().map(|recorder| recorder.finish())
+    }
+
+    pub fn is_recording_input(&self) -> bool {
+        self.input_recorder.is_some()
+    }
+
+    // Feeds the recorded events back frame by frame, with the recorded frame
+    // times in place of measured ones. Live input is ignored until it ends.
+    pub fn start_input_replay(&mut self, recording: InputRecording) {
+        self.input_replay = Some(InputReplay::new(recording));
+    }
+
+    pub fn stop_input_replay(&mut self) {
+        self.input_replay = None;
+    }
+
+    pub fn is_replaying_input(&self) -> bool {
+        self.input_replay.is_some()
+    }
+
+    // Call once the frame is rendered, resets per frame input
+    pub fn end_frame(&mut self) {
+        self.input.end_frame();
+    }
+
+    // Measures the frame time, call once per frame before `pre_render`.
+    // Returns how many fixed updates are due this frame.
+    pub fn update_clock(&mut self) -> u32 {
         let current_instant = Instant::now();
-        let dt = current_instant.duration_since(self.last_frame_instant);
+        let mut dt = current_instant.duration_since(self.last_frame_instant);
         self.last_frame_instant = current_instant;
 
+        if let Some(input_replay) = &mut self.input_replay {
+            match input_replay.next_frame() {
+                Some((events, recorded_dt)) => {
+                    for event in events.iter() {
+                        self.dispatch_input(event);
+                    }
+                    dt = recorded_dt;
+                }
+                None => {
+                    log!("Input replay finished");
+                    self.input_replay = None;
+                }
+            }
+        }
+
+        self.update_clock_with_dt(dt)
+    }
+
+    // Same as `update_clock` but with a caller provided frame time, used to
+    // step the scene deterministically
+    pub fn update_clock_with_dt(&mut self, dt: Duration) -> u32 {
         // Frame Count and FPS
         self.fps = (1.0 / dt.as_secs_f64()) as usize;
         self.frame_count += 1;
         self.frame_time = dt;
         log!(format!("FPS: {}", self.fps));
 
-        // Pre-render the scene
-        if let Some(scene) = &mut self.active_scene {
-            scene.pre_render(&self.v_backend, dt.as_secs_f32());
+        if let Some(input_recorder) = &mut self.input_recorder {
+            input_recorder.record_frame(dt);
+        }
+
+        // Scaled simulation time
+        self.delta_time = if self.paused {
+            Duration::ZERO
+        } else {
+            dt.mul_f32(self.time_scale)
+        };
+
+        match &mut self.fixed_timestep {
+            Some(fixed_timestep) => fixed_timestep.advance(self.delta_time),
+            None => 0,
+        }
+    }
+
+    pub fn pre_render(&mut self) {
+        log!("Game Engine pre render");
+
+        self.finish_preloads();
+        let ctx = SystemContext {
+            v_backend: &self.v_backend,
+            input: &self.input,
+            dt: self.delta_time.as_secs_f32(),
+        };
+        // Scenes paused by an overlay keep their state, only what they are
+        // drawn with is brought up to date
+        for name in self.scenes.get_paused_names() {
+            if let Some(scene) = self.scenes.get_mut(&name) {
+                scene.pre_render_paused(&ctx);
+            }
+        }
+        for name in self.scenes.get_running_names() {
+            if let Some(scene) = self.scenes.get_mut(&name) {
+                scene.pre_render(&ctx);
+            }
+        }
+    }
+
+    pub fn pre_render_with_dt(&mut self, dt: Duration) {
+        self.update_clock_with_dt(dt);
+        self.pre_render();
+    }
+
+    // Frame time after time scale and pause are applied
+    pub fn get_delta_time(&self) -> Duration {
+        self.delta_time
+    }
+
+    pub fn get_time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        assert!(time_scale >= 0.0, "time scale can't be negative");
+        self.time_scale = time_scale;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Paused, simulation time stands still but frames are still rendered
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn enable_fixed_timestep(&mut self, rate_hz: f32, max_steps_per_frame: u32) {
+        self.fixed_timestep = Some(FixedTimestep::new(rate_hz, max_steps_per_frame));
+    }
+
+    pub fn disable_fixed_timestep(&mut self) {
+        self.fixed_timestep = None;
+    }
+
+    pub fn get_fixed_delta_time(&self) -> Option<Duration> {
+        self.fixed_timestep
+            .as_ref()
+            .map(|fixed_timestep| fixed_timestep.step)
+    }
+
+    // Blend factor between the previous and current fixed update state.
+    // Always 1 without a fixed timestep.
+    pub fn get_interpolation_alpha(&self) -> f32 {
+        match &self.fixed_timestep {
+            Some(fixed_timestep) => fixed_timestep.alpha(),
+            None => 1.0,
         }
     }
 
@@ -218,28 +848,62 @@ impl GameEngine {
         // render
         log!("Game Engine render");
 
+        // A minimized window has no extent to render into
+        let size = window.inner_size();
+        if size.width == 0 || size.height == 0 {
+            log!("Window has zero extent, skipping render");
+            return;
+        }
+
         let render_result = self.v_backend.render(|info| self.render_scene(&info));
+        if let VFrameRenderResult::Ok = render_result {
+            self.on_frame_submitted();
+        }
 
         // check render result
-        if let Some(event) = self.v_backend.check_render_issues(window, render_result) {
-            self.scene_renderer.handle_backend_event(&event);
-            if let Some(scene) = &mut self.active_scene {
-                scene.handle_backend_event(&event);
-            }
+        if let Some(VBackendEvent::UpdateFramebuffers(..)) =
+            self.v_backend.check_render_issues(window, render_result)
+        {
+            self.update_framebuffers();
+            log!("Update framebuffer, frame: {}", self.frame_count);
+        }
+    }
 
-            match event {
-                VBackendEvent::UpdateFramebuffers(..) => {
-                    log!("Update framebuffer, frame: {}", self.frame_count);
-                }
-                _ => (),
-            }
+    pub fn render_headless(&mut self) {
+        log!("Game Engine headless render");
+
+        assert!(
+            self.v_backend.is_headless(),
+            "render_headless requires an engine created with GameEngine::new_headless"
+        );
+        if let VFrameRenderResult::Ok = self.v_backend.render(|info| self.render_scene(&info)) {
+            self.on_frame_submitted();
         }
     }
 
+    fn on_frame_submitted(&mut self) {
+        for scene in self.scenes.iter_mut() {
+            scene.advance_deletion_queue(&self.v_backend);
+        }
+        self.release_unused_assets();
+        self.deletion_queue.advance(&self.v_backend);
+    }
+
+    // Waits for the device and returns the last frame rendered headless
+    pub fn read_frame(&mut self) -> Option<RgbaImage> {
+        let extent = self.v_backend.v_render_target.image_extent();
+        let pixels = self.v_backend.read_pixels()?;
+        RgbaImage::from_raw(extent.width, extent.height, pixels)
+    }
+
     fn render_scene(&self, ctx: &VFrameRenderContext) {
-        if let Some(scene) = &self.active_scene {
+        let stack = self.scenes.get_stack();
+        if !stack.is_empty() {
             log!("Scene render started");
-            let recordables: [&dyn RecordableScene; 1] = [scene];
+            let recordables: Vec<&dyn RecordableScene> = stack
+                .into_iter()
+                .map(|scene| scene as &dyn RecordableScene)
+                .collect();
             self.scene_renderer.render(
                 &self.v_backend.v_device,
                 &self.materials_manager,
@@ -254,9 +918,16 @@ impl GameEngine {
 
     pub fn destroy(mut self) {
         self.v_backend.v_device.wait_till_idle();
-        if let Some(scene) = &self.active_scene {
+        // Scenes still being read are dropped when their thread ends
+        self.preloading.clear();
+        for mut scene in self.scenes.drain() {
             scene.destroy(&self.v_backend);
         }
+        self.deletion_queue.flush(&self.v_backend);
+        // Including models of prefabs that were never unloaded
+        for model in self.models.drain(..) {
+            model.destroy(&self.v_backend);
+        }
         // Destroy all engine-owned textures
         for (_, tex) in self.textures.drain() {
             tex.destroy(&self.v_backend);
@@ -0,0 +1,52 @@
+use crate::{constants::MAX_FRAMES_IN_FLIGHT, vulkan_backend::backend::VBackend};
+
+type Deletion = Box<dyn FnOnce(&VBackend)>;
+
+// GPU resources that frames in flight may still read. Each one is destroyed
+// once MAX_FRAMES_IN_FLIGHT more frames were submitted after it was queued.
+#[derive(Default)]
+pub struct DeletionQueue {
+    pending: Vec<(usize, Deletion)>,
+    submitted_frames: usize,
+}
+
+impl DeletionQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, deletion: impl FnOnce(&VBackend) + 'static) {
+        self.pending
+            .push((self.submitted_frames, Box::new(deletion)));
+    }
+
+    // Call after every submitted frame
+    pub fn advance(&mut self, v_backend: &VBackend) {
+        self.submitted_frames += 1;
+
+        let submitted_frames = self.submitted_frames;
+        let (ready, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(queued_at, _)| submitted_frames - queued_at >= MAX_FRAMES_IN_FLIGHT);
+        self.pending = pending;
+
+        for (_, deletion) in ready {
+            deletion(v_backend);
+        }
+    }
+
+    // Destroys everything at once, only safe while the device is idle
+    pub fn flush(&mut self, v_backend: &VBackend) {
+        for (_, deletion) in self.pending.drain(..) {
+            deletion(v_backend);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
@@ -1,15 +1,20 @@
-use crate::vulkan_backend::{
-    descriptor::{
-        VDescriptorPool, VDescriptorSet, VDescriptorSetLayout,
-        config::{VDescriptorPoolConfig, VDescriptorPoolTypeConfig},
+use crate::{
+    core::gpu::deletion_queue::DeletionQueue,
+    vulkan_backend::{
+        descriptor::{
+            VDescriptorPool, VDescriptorSet, VDescriptorSetLayout,
+            config::{VDescriptorPoolConfig, VDescriptorPoolTypeConfig},
+        },
+        device::VDevice,
     },
-    device::VDevice,
 };
 use ash::vk;
 
 pub struct MaterialsManager {
     descriptor_pool: VDescriptorPool,
-    descriptor_sets: Vec<VDescriptorSet>,
+    // None for freed materials, their index is handed out again
+    descriptor_sets: Vec<Option<VDescriptorSet>>,
+    free_indices: Vec<usize>,
 }
 
 impl MaterialsManager {
@@ -23,14 +28,18 @@ impl MaterialsManager {
                         count: 100,
                     }],
                     max_sets: 100,
+                    flags: vk::DescriptorPoolCreateFlags::FREE_DESCRIPTOR_SET,
                 },
             ),
             descriptor_sets: vec![],
+            free_indices: vec![],
         }
     }
 
     pub fn get_set_at(&self, index: usize) -> &VDescriptorSet {
-        return &self.descriptor_sets[index];
+        self.descriptor_sets[index]
+            .as_ref()
+            .expect("material was freed")
     }
 
     pub fn allocate_material(
@@ -38,12 +47,24 @@ impl MaterialsManager {
         v_device: &VDevice,
         v_layout: &VDescriptorSetLayout,
     ) -> usize {
-        self.descriptor_sets.push(VDescriptorSet::new(
-            v_device,
-            &self.descriptor_pool,
-            v_layout,
-        ));
-        return self.descriptor_sets.len() - 1;
+        let set = VDescriptorSet::new(v_device, &self.descriptor_pool, v_layout);
+        if let Some(index) = self.free_indices.pop() {
+            self.descriptor_sets[index] = Some(set);
+            return index;
+        }
+        self.descriptor_sets.push(Some(set));
+        self.descriptor_sets.len() - 1
+    }
+
+    // The set is freed once the frames in flight are done with it, the
+    // index can be handed out again right away
+    pub fn free_material(&mut self, index: usize, deletion_queue: &mut DeletionQueue) {
+        let set = self.descriptor_sets[index]
+            .take()
+            .expect("material was already freed");
+        self.free_indices.push(index);
+        let pool = self.descriptor_pool.pool;
+        deletion_queue.push(move |v_backend| set.free(&v_backend.v_device, pool));
     }
 
     pub fn destroy(&self, v_device: &VDevice) {
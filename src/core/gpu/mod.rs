@@ -1,3 +1,4 @@
+pub mod deletion_queue;
 pub mod directional_light_uniform;
 pub mod global_uniform;
 pub mod materials_manager;
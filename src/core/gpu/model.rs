@@ -5,17 +5,65 @@ use crate::{
     vulkan_backend::{
         backend::VBackend,
         device::VDevice,
-        memory::{VBuffer, VBufferConfig},
+        memory::{VBuffer, VBufferConfig, VMemoryState},
         vertex_input::Vertex3D,
     },
 };
 
+// Vertices and indices read from a mesh file, before they are uploaded. Can
+// be loaded on any thread.
+pub struct MeshData {
+    pub vertices: Vec<Vertex3D>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    pub fn from_obj(obj_path: &str) -> Self {
+        let (models, _) =
+            tobj::load_obj(obj_path, &tobj::GPU_LOAD_OPTIONS).expect("failed to load obj model");
+        assert!(models.len() > 0, "No models found in obj file");
+
+        let mesh = &models[0].mesh;
+        let total_vertices = mesh.positions.len() / 3;
+
+        let mut vertices: Vec<Vertex3D> = vec![];
+        for i in 0..total_vertices {
+            vertices.push(Vertex3D {
+                pos: [
+                    mesh.positions[3 * i],
+                    mesh.positions[3 * i + 1],
+                    mesh.positions[3 * i + 2],
+                ],
+                color: [1.0, 1.0, 1.0],
+                normal: [
+                    mesh.normals[3 * i],
+                    mesh.normals[3 * i + 1],
+                    mesh.normals[3 * i + 2],
+                ],
+                uv: [mesh.texcoords[2 * i], mesh.texcoords[2 * i + 1]],
+            });
+        }
+
+        Self {
+            vertices,
+            indices: mesh.indices.clone(),
+        }
+    }
+}
+
+// The buffers stay private to this file, which only ever builds them device
+// local, filled through a staging buffer, see `Model::new`
 pub struct Model {
-    pub v_buffer: VBuffer,
-    pub i_buffer: VBuffer,
-    pub index_count: u32,
+    v_buffer: VBuffer,
+    i_buffer: VBuffer,
+    index_count: u32,
 }
 
+// Shared through `Structure3D` with parallel systems. The buffers are never
+// mapped, so the only thing shared is Vulkan handles.
+unsafe impl Send for Model {}
+unsafe impl Sync for Model {}
+
 impl Model {
     pub fn new(v_backend: &VBackend, vertices: &[Vertex3D], indices: &[u32]) -> Self {
         let mut v_buffer = VBuffer::new(
@@ -62,6 +110,12 @@ impl Model {
             indices_data_ptr,
         );
 
+        // Checked since `Send` and `Sync` rely on it
+        assert!(
+            matches!(v_buffer.v_memory.state, VMemoryState::UNMAPPED)
+                && matches!(i_buffer.v_memory.state, VMemoryState::UNMAPPED),
+            "model buffers must not stay mapped"
+        );
         Self {
             v_buffer,
             i_buffer,
@@ -70,32 +124,11 @@ impl Model {
     }
 
     pub fn from_obj(v_backend: &VBackend, obj_path: &str) -> Self {
-        let (models, _) =
-            tobj::load_obj(obj_path, &tobj::GPU_LOAD_OPTIONS).expect("failed to load obj model");
-        assert!(models.len() > 0, "No models found in obj file");
-
-        let mesh = &models[0].mesh;
-        let total_vertices = mesh.positions.len() / 3;
-
-        let mut vertices: Vec<Vertex3D> = vec![];
-        for i in 0..total_vertices {
-            vertices.push(Vertex3D {
-                pos: [
-                    mesh.positions[3 * i],
-                    mesh.positions[3 * i + 1],
-                    mesh.positions[3 * i + 2],
-                ],
-                color: [1.0, 1.0, 1.0],
-                normal: [
-                    mesh.normals[3 * i],
-                    mesh.normals[3 * i + 1],
-                    mesh.normals[3 * i + 2],
-                ],
-                uv: [mesh.texcoords[2 * i], mesh.texcoords[2 * i + 1]],
-            });
-        }
+        Self::from_mesh_data(v_backend, &MeshData::from_obj(obj_path))
+    }
 
-        Self::new(v_backend, &vertices, &mesh.indices)
+    pub fn from_mesh_data(v_backend: &VBackend, mesh_data: &MeshData) -> Self {
+        Self::new(v_backend, &mesh_data.vertices, &mesh_data.indices)
     }
 
     pub fn destroy(&self, v_backend: &VBackend) {
@@ -18,6 +18,7 @@ use ash::vk::{self, Rect2D};
 pub struct GeometryLightingRenderStageConfig {
     pub color_format: vk::Format,
     pub depth_format: vk::Format,
+    pub final_color_layout: vk::ImageLayout,
 }
 
 pub struct GeometryLightingRenderStage {
@@ -39,7 +40,7 @@ impl GeometryLightingRenderStage {
             .load_op(vk::AttachmentLoadOp::CLEAR)
             .store_op(vk::AttachmentStoreOp::STORE)
             .initial_layout(vk::ImageLayout::UNDEFINED)
-            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+            .final_layout(config.final_color_layout);
 
         let color_attachment_ref = vk::AttachmentReference::default()
             .attachment(0)
@@ -83,6 +84,19 @@ impl GeometryLightingRenderStage {
                 .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
                 .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE),
         ]);
+        // Offscreen frames are copied out right after the pass, the copy must
+        // wait for the color writes and the transition to the final layout
+        if config.final_color_layout == vk::ImageLayout::TRANSFER_SRC_OPTIMAL {
+            subpass_dependencies.push(
+                vk::SubpassDependency::default()
+                    .src_subpass(0)
+                    .dst_subpass(vk::SUBPASS_EXTERNAL)
+                    .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+                    .dst_stage_mask(vk::PipelineStageFlags::TRANSFER)
+                    .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+                    .dst_access_mask(vk::AccessFlags::TRANSFER_READ),
+            );
+        }
 
         let attachments = [color_attachment, depth_attachment];
         let render_pass_info = vk::RenderPassCreateInfo::default()
@@ -154,21 +168,28 @@ impl GeometryLightingRenderStage {
             },
         );
 
-        let pipeline_infos = vec![VPipelineInfo::new(
-            &v_device,
-            VPipelineInfoConfig {
-                binding_descriptions: vertex_binding_descriptions.clone(),
-                attribute_descriptions: vertex_attribute_descriptions.clone(),
-                vertex_shader_file: Some("assets/shaders/shader.vert".into()),
-                fragment_shader_file: Some("assets/shaders/shader.frag".into()),
-            },
-            Some(&model_push_constant),
-            &[
-                &global_uniform_layout,
-                &lights_uniform_layout,
-                &image_sampler_layout,
-            ],
-        )];
+        // The second pipeline is for reverse-Z cameras, it has the same layout
+        let pipeline_infos: Vec<VPipelineInfo> = [vk::CompareOp::LESS, vk::CompareOp::GREATER]
+            .into_iter()
+            .map(|depth_compare_op| {
+                VPipelineInfo::new(
+                    v_device,
+                    VPipelineInfoConfig {
+                        binding_descriptions: vertex_binding_descriptions.clone(),
+                        attribute_descriptions: vertex_attribute_descriptions.clone(),
+                        vertex_shader_file: Some("assets/shaders/shader.vert".into()),
+                        fragment_shader_file: Some("assets/shaders/shader.frag".into()),
+                        depth_compare_op,
+                    },
+                    Some(&model_push_constant),
+                    &[
+                        &global_uniform_layout,
+                        &lights_uniform_layout,
+                        &image_sampler_layout,
+                    ],
+                )
+            })
+            .collect();
         let pipelines = create_pipelines_from_infos(v_device, render_pass, &pipeline_infos);
 
         let descriptor_set_layouts = vec![
@@ -232,8 +253,8 @@ impl GeometryLightingRenderStage {
     pub fn init_framebuffers(
         &mut self,
         v_device: &VDevice,
-        ids: &Vec<Id>,
-        color_views: &Vec<VImageView>,
+        ids: &[Id],
+        color_views: &[VImageView],
         depth_view: &VImageView,
         image_extent: vk::Extent2D,
     ) {
@@ -285,6 +306,36 @@ impl GeometryLightingRenderStage {
         };
     }
 
+    pub fn get_pipeline(&self, reverse_z: bool) -> vk::Pipeline {
+        self.pipelines[reverse_z as usize]
+    }
+
+    // Inside the render pass, so what is drawn next ends up in front of
+    // everything drawn so far. Reverse-Z clears to 0, the far end.
+    pub fn clear_depth(&self, v_device: &VDevice, cmd: vk::CommandBuffer, reverse_z: bool) {
+        let render_area = self.render_area.expect("render_area not set");
+        let mut clear_value = vk::ClearValue::default();
+        clear_value.depth_stencil = vk::ClearDepthStencilValue {
+            depth: if reverse_z { 0.0 } else { 1.0 },
+            stencil: 0,
+        };
+        let attachment = vk::ClearAttachment {
+            aspect_mask: vk::ImageAspectFlags::DEPTH,
+            color_attachment: 0,
+            clear_value,
+        };
+        let rect = vk::ClearRect {
+            rect: render_area,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        unsafe {
+            v_device
+                .device
+                .cmd_clear_attachments(cmd, &[attachment], &[rect]);
+        }
+    }
+
     pub fn end(&self, v_device: &VDevice, ctx: &VFrameRenderContext) {
         unsafe {
             v_device.device.cmd_end_render_pass(ctx.cmd);
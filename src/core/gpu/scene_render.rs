@@ -9,7 +9,7 @@ use crate::{
     },
     vulkan_backend::{
         backend::VBackend, backend_event::VBackendEvent, descriptor::VDescriptorSetLayout,
-        device::VDevice, frame::context::VFrameRenderContext, swapchain::VSwapchain,
+        device::VDevice, frame::context::VFrameRenderContext, render_target::VRenderTarget,
     },
 };
 pub trait RecordableScene {
@@ -20,6 +20,9 @@ pub trait RecordableScene {
         materials_m: &MaterialsManager,
         scene_r: &SceneRenderer,
     );
+
+    // Whether it is drawn with a reversed depth test, see `Projection::ReverseZ`
+    fn is_reverse_z(&self) -> bool;
 }
 
 pub trait DrawableSceneElement {
@@ -38,26 +41,31 @@ impl SceneRenderer {
         let gl_rs = GeometryLightingRenderStage::new(
             &v_backend.v_device,
             GeometryLightingRenderStageConfig {
-                color_format: v_backend.v_swapchain.v_images[0].config.format,
-                depth_format: v_backend.v_swapchain.depth_format,
+                color_format: v_backend.v_render_target.color_format(),
+                depth_format: v_backend.v_render_target.depth_format(),
+                final_color_layout: v_backend.v_render_target.final_color_layout(),
             },
         );
 
         let mut scene_renderer = Self { gl_rs };
 
-        scene_renderer.init_gl_rs(&v_backend.v_device, &v_backend.v_swapchain);
+        scene_renderer.init_gl_rs(&v_backend.v_device, &v_backend.v_render_target);
 
         scene_renderer
     }
 
-    pub fn init_gl_rs(&mut self, v_device: &VDevice, v_swapchain: &VSwapchain) {
-        self.gl_rs.init_framebuffers(
-            v_device,
-            &v_swapchain.image_ids,
-            &v_swapchain.v_image_views,
-            &v_swapchain.depth_v_image_view,
-            v_swapchain.image_extent,
-        );
+    pub fn init_gl_rs(&mut self, v_device: &VDevice, v_render_target: &VRenderTarget) {
+        match v_render_target.depth_v_image_view() {
+            Some(depth_v_image_view) => self.gl_rs.init_framebuffers(
+                v_device,
+                v_render_target.image_ids(),
+                v_render_target.v_image_views(),
+                depth_v_image_view,
+                v_render_target.image_extent(),
+            ),
+            // Suspended, framebuffers must not outlive the swapchain images
+            None => self.gl_rs.v_framebuffers.remove_all_framebuffers(v_device),
+        }
     }
 
     pub fn get_pipeline_layout(&self) -> &vk::PipelineLayout {
@@ -78,8 +86,8 @@ impl SceneRenderer {
 
     pub fn handle_backend_event(&mut self, event: &VBackendEvent) {
         match event {
-            VBackendEvent::UpdateFramebuffers(v_device, v_swapchain) => {
-                self.init_gl_rs(v_device, v_swapchain);
+            VBackendEvent::UpdateFramebuffers(v_device, v_render_target) => {
+                self.init_gl_rs(v_device, v_render_target);
             }
             _ => {}
         }
@@ -95,15 +103,20 @@ impl SceneRenderer {
         // Geometry Pass
         self.gl_rs.start(v_device, ctx.cmd, &ctx.image_id);
 
-        unsafe {
-            v_device.device.cmd_bind_pipeline(
-                ctx.cmd,
-                vk::PipelineBindPoint::GRAPHICS,
-                self.gl_rs.pipelines[0],
-            )
-        };
-
-        for recordable in recordables.iter() {
+        // Later scenes are overlays, drawn over the earlier ones. The pass
+        // starts with the depth cleared for the regular depth test.
+        for (index, recordable) in recordables.iter().enumerate() {
+            let reverse_z = recordable.is_reverse_z();
+            if index > 0 || reverse_z {
+                self.gl_rs.clear_depth(v_device, ctx.cmd, reverse_z);
+            }
+            unsafe {
+                v_device.device.cmd_bind_pipeline(
+                    ctx.cmd,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    self.gl_rs.get_pipeline(reverse_z),
+                )
+            };
             recordable.record_scene(v_device, ctx.cmd, materials_manager, self);
         }
 
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use ash::vk;
 
 use crate::{
-    shared::types::Id,
+    core::{ecs::entities::entity::Entity, gpu::deletion_queue::DeletionQueue},
     vulkan_backend::{
         backend::VBackend,
         memory::image::{VImage, VImageConfig, image_view::VImageView, sampler::VSampler},
@@ -11,9 +11,9 @@ use crate::{
 };
 
 pub struct ShadowMapping {
-    pub spot_light_maps: HashMap<Id, VImage>,
-    pub spot_light_views: HashMap<Id, VImageView>,
-    pub spot_light_samplers: HashMap<Id, VSampler>,
+    pub spot_light_maps: HashMap<Entity, VImage>,
+    pub spot_light_views: HashMap<Entity, VImageView>,
+    pub spot_light_samplers: HashMap<Entity, VSampler>,
 }
 
 impl ShadowMapping {
@@ -25,8 +25,8 @@ impl ShadowMapping {
         }
     }
 
-    pub fn add_spot_light(&mut self, v_backend: &VBackend, entity_id: Id) {
-        let extent = v_backend.v_swapchain.image_extent;
+    pub fn add_spot_light(&mut self, v_backend: &VBackend, entity_id: Entity) {
+        let extent = v_backend.v_render_target.image_extent();
         let spot_light_map = VImage::new(
             &v_backend.v_device,
             &v_backend.v_physical_device,
@@ -61,7 +61,16 @@ impl ShadowMapping {
             .insert(entity_id, spot_light_sampler);
     }
 
-    pub fn remove_spot_light(&mut self, v_backend: &VBackend, entity_id: &Id) {
+    // Shadow maps match the render target extent, so they are rebuilt after a resize
+    pub fn recreate(&mut self, v_backend: &VBackend) {
+        let entity_ids: Vec<Entity> = self.spot_light_maps.keys().copied().collect();
+        for entity_id in entity_ids {
+            self.remove_spot_light(v_backend, &entity_id);
+            self.add_spot_light(v_backend, entity_id);
+        }
+    }
+
+    pub fn remove_spot_light(&mut self, v_backend: &VBackend, entity_id: &Entity) {
         if let Some(image) = self.spot_light_maps.remove(entity_id) {
             image.destroy(&v_backend.v_device, &v_backend.v_memory_manager);
         }
@@ -73,6 +82,28 @@ impl ShadowMapping {
         }
     }
 
+    // For lights removed while frames in flight may still sample their map
+    pub fn queue_spot_light_removal(
+        &mut self,
+        deletion_queue: &mut DeletionQueue,
+        entity_id: &Entity,
+    ) {
+        let image = self.spot_light_maps.remove(entity_id);
+        let view = self.spot_light_views.remove(entity_id);
+        let sampler = self.spot_light_samplers.remove(entity_id);
+        deletion_queue.push(move |v_backend| {
+            if let Some(sampler) = sampler {
+                sampler.destroy(&v_backend.v_device);
+            }
+            if let Some(view) = view {
+                view.destroy(&v_backend.v_device);
+            }
+            if let Some(image) = image {
+                image.destroy(&v_backend.v_device, &v_backend.v_memory_manager);
+            }
+        });
+    }
+
     pub fn destroy(&mut self, v_backend: &VBackend) {
         for (_, image) in self.spot_light_maps.drain() {
             image.destroy(&v_backend.v_device, &v_backend.v_memory_manager);
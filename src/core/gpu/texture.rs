@@ -1,4 +1,5 @@
 use ash::vk::{self, Extent3D};
+use image::RgbaImage;
 
 use crate::vulkan_backend::memory::image::{VImage, VImageConfig};
 use crate::vulkan_backend::{
@@ -14,11 +15,21 @@ pub struct ImageTexture {
 
 impl ImageTexture {
     pub fn new(v_backend: &VBackend, image_path: &str, format: vk::Format) -> Self {
-        let opened_image = image::open(image_path).expect("failed to open image texture");
-        let image_rgba = opened_image.to_rgba8();
+        Self::from_rgba(v_backend, &Self::read_rgba(image_path), format)
+    }
+
+    // Decodes the image file without touching the GPU, so it can run on any
+    // thread
+    pub fn read_rgba(image_path: &str) -> RgbaImage {
+        image::open(image_path)
+            .expect("failed to open image texture")
+            .to_rgba8()
+    }
+
+    pub fn from_rgba(v_backend: &VBackend, image_rgba: &RgbaImage, format: vk::Format) -> Self {
         let image_extent = Extent3D {
-            width: opened_image.width(),
-            height: opened_image.height(),
+            width: image_rgba.width(),
+            height: image_rgba.height(),
             depth: 1,
         };
         let image_size = image_extent.width as u64 * image_extent.height as u64 * 4;
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+
+// How the app treats the OS cursor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorMode {
+    Normal,
+    // Invisible over the window but free to leave it
+    Hidden,
+    // Invisible and locked to the window, used for mouse-look
+    Grabbed,
+}
@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputBinding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+// Axis value is +1 while `positive` is held, -1 while `negative` is held
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub positive: InputBinding,
+    pub negative: InputBinding,
+}
+
+// Named actions and axes bound to physical inputs. Stored as RON:
+// (
+//     actions: { "jump": [Key(Space)], "fire": [Mouse(Left)] },
+//     axes: { "move_x": [(positive: Key(KeyD), negative: Key(KeyA))] },
+// )
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputMap {
+    #[serde(default)]
+    pub actions: HashMap<String, Vec<InputBinding>>,
+    #[serde(default)]
+    pub axes: HashMap<String, Vec<AxisBinding>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_file(path: &str) -> Self {
+        let contents = std::fs::read_to_string(path).expect("failed to read input map file");
+        ron::from_str(&contents).expect("failed to parse input map file")
+    }
+
+    pub fn save_to_file(&self, path: &str) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("failed to serialize input map");
+        std::fs::write(path, contents).expect("failed to write input map file");
+    }
+
+    pub fn bind_action(&mut self, action: &str, binding: InputBinding) {
+        self.actions.entry(action.into()).or_default().push(binding);
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, positive: InputBinding, negative: InputBinding) {
+        self.axes
+            .entry(axis.into())
+            .or_default()
+            .push(AxisBinding { positive, negative });
+    }
+
+    pub fn unbind_action(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn unbind_axis(&mut self, axis: &str) {
+        self.axes.remove(axis);
+    }
+
+    pub fn get_action_bindings(&self, action: &str) -> &[InputBinding] {
+        self.actions
+            .get(action)
+            .map(|b| b.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn get_axis_bindings(&self, axis: &str) -> &[AxisBinding] {
+        self.axes.get(axis).map(|b| b.as_slice()).unwrap_or(&[])
+    }
+}
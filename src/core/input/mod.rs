@@ -0,0 +1,9 @@
+pub mod cursor;
+pub mod map;
+pub mod recording;
+pub mod state;
+
+pub use cursor::CursorMode;
+pub use map::{AxisBinding, InputBinding, InputMap};
+pub use recording::{InputRecording, RecordedInputEvent};
+pub use state::InputState;
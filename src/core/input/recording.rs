@@ -0,0 +1,128 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    ecs::types::{
+        CursorMovedEvent, KeyboardInputEvent, MouseButtonEvent, MouseMotionEvent, MouseWheelEvent,
+    },
+    input::cursor::CursorMode,
+};
+
+// Everything that reaches `GameEngine` as input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordedInputEvent {
+    KeyboardInput(KeyboardInputEvent),
+    CursorMoved(CursorMovedEvent),
+    MouseButton(MouseButtonEvent),
+    MouseWheel(MouseWheelEvent),
+    MouseMotion(MouseMotionEvent),
+    CursorMode(CursorMode),
+    // Focus was lost and every held key and button released
+    ReleaseAll,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    // Number of frames recorded before the event arrived, so it is consumed
+    // by frame `frame + 1`
+    pub frame: usize,
+    // Seconds since the recording started
+    pub timestamp: f64,
+    pub event: RecordedInputEvent,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InputRecording {
+    // Raw frame time of every recorded frame, kept exact so the replay
+    // steps the clock by the same amounts
+    pub frame_times: Vec<Duration>,
+    pub events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    pub fn from_file(path: &str) -> Self {
+        let contents = std::fs::read_to_string(path).expect("failed to read input recording");
+        ron::from_str(&contents).expect("failed to parse input recording")
+    }
+
+    pub fn save_to_file(&self, path: &str) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("failed to serialize input recording");
+        std::fs::write(path, contents).expect("failed to write input recording");
+    }
+
+    pub fn get_frame_count(&self) -> usize {
+        self.frame_times.len()
+    }
+}
+
+pub struct InputRecorder {
+    recording: InputRecording,
+    start_instant: Instant,
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputRecorder {
+    pub fn new() -> Self {
+        Self {
+            recording: InputRecording::default(),
+            start_instant: Instant::now(),
+        }
+    }
+
+    pub fn record_event(&mut self, event: &RecordedInputEvent) {
+        self.recording.events.push(RecordedEvent {
+            frame: self.recording.frame_times.len(),
+            timestamp: self.start_instant.elapsed().as_secs_f64(),
+            event: event.clone(),
+        });
+    }
+
+    pub fn record_frame(&mut self, dt: Duration) {
+        self.recording.frame_times.push(dt);
+    }
+
+    pub fn finish(self) -> InputRecording {
+        self.recording
+    }
+}
+
+pub struct InputReplay {
+    recording: InputRecording,
+    frame: usize,
+    next_event: usize,
+}
+
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            next_event: 0,
+        }
+    }
+
+    // Events to apply before the next frame and that frame's recorded dt.
+    // None once every recorded frame has been replayed.
+    pub fn next_frame(&mut self) -> Option<(Vec<RecordedInputEvent>, Duration)> {
+        let dt = *self.recording.frame_times.get(self.frame)?;
+
+        let mut events = vec![];
+        while let Some(recorded) = self.recording.events.get(self.next_event) {
+            if recorded.frame > self.frame {
+                break;
+            }
+            events.push(recorded.event.clone());
+            self.next_event += 1;
+        }
+        self.frame += 1;
+
+        Some((events, dt))
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+
+use nalgebra::Vector2;
+use winit::{
+    event::{ElementState, MouseButton},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::core::{
+    ecs::types::{
+        CursorMovedEvent, KeyboardInputEvent, MouseButtonEvent, MouseMotionEvent, MouseWheelEvent,
+    },
+    input::map::{InputBinding, InputMap},
+};
+
+// Keyboard and mouse state for the current frame. The `just_*` queries and
+// the mouse deltas cover the events received since the last `end_frame`.
+#[derive(Default)]
+pub struct InputState {
+    keys_down: HashSet<KeyCode>,
+    keys_just_pressed: HashSet<KeyCode>,
+    keys_just_released: HashSet<KeyCode>,
+    mouse_buttons_down: HashSet<MouseButton>,
+    mouse_buttons_just_pressed: HashSet<MouseButton>,
+    mouse_buttons_just_released: HashSet<MouseButton>,
+    cursor_position: Option<Vector2<f64>>,
+    mouse_delta: Vector2<f32>,
+    scroll_delta: Vector2<f32>,
+    pub input_map: InputMap,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn handle_keyboard_input(&mut self, event: &KeyboardInputEvent) {
+        let PhysicalKey::Code(key) = event.key else {
+            return;
+        };
+        match event.state {
+            ElementState::Pressed => {
+                // Key repeat doesn't count as a new press
+                if self.keys_down.insert(key) {
+                    self.keys_just_pressed.insert(key);
+                }
+            }
+            ElementState::Released => {
+                if self.keys_down.remove(&key) {
+                    self.keys_just_released.insert(key);
+                }
+            }
+        }
+    }
+
+    pub fn handle_mouse_input(&mut self, event: &MouseButtonEvent) {
+        match event.state {
+            ElementState::Pressed => {
+                if self.mouse_buttons_down.insert(event.button) {
+                    self.mouse_buttons_just_pressed.insert(event.button);
+                }
+            }
+            ElementState::Released => {
+                if self.mouse_buttons_down.remove(&event.button) {
+                    self.mouse_buttons_just_released.insert(event.button);
+                }
+            }
+        }
+    }
+
+    pub fn handle_cursor_moved(&mut self, event: &CursorMovedEvent) {
+        self.cursor_position = Some(Vector2::new(event.x, event.y));
+    }
+
+    pub fn handle_mouse_motion(&mut self, event: &MouseMotionEvent) {
+        self.mouse_delta += Vector2::new(event.delta_x as f32, event.delta_y as f32);
+    }
+
+    pub fn handle_mouse_wheel(&mut self, event: &MouseWheelEvent) {
+        self.scroll_delta += Vector2::new(event.delta_x, event.delta_y);
+    }
+
+    // Releases everything, e.g. when the window loses focus and release
+    // events would never arrive
+    pub fn release_all(&mut self) {
+        self.keys_just_released.extend(self.keys_down.drain());
+        self.mouse_buttons_just_released
+            .extend(self.mouse_buttons_down.drain());
+    }
+
+    pub fn end_frame(&mut self) {
+        self.keys_just_pressed.clear();
+        self.keys_just_released.clear();
+        self.mouse_buttons_just_pressed.clear();
+        self.mouse_buttons_just_released.clear();
+        self.mouse_delta = Vector2::zeros();
+        self.scroll_delta = Vector2::zeros();
+    }
+
+    // Keys
+    pub fn is_pressed(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+
+    pub fn just_released(&self, key: KeyCode) -> bool {
+        self.keys_just_released.contains(&key)
+    }
+
+    // Mouse
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_down.contains(&button)
+    }
+
+    pub fn mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_just_pressed.contains(&button)
+    }
+
+    pub fn mouse_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_just_released.contains(&button)
+    }
+
+    pub fn get_cursor_position(&self) -> Option<Vector2<f64>> {
+        self.cursor_position
+    }
+
+    // Raw mouse motion, keeps working while the cursor is grabbed
+    pub fn get_mouse_delta(&self) -> Vector2<f32> {
+        self.mouse_delta
+    }
+
+    pub fn get_scroll_delta(&self) -> Vector2<f32> {
+        self.scroll_delta
+    }
+
+    // Bindings
+    pub fn is_binding_pressed(&self, binding: InputBinding) -> bool {
+        match binding {
+            InputBinding::Key(key) => self.is_pressed(key),
+            InputBinding::Mouse(button) => self.is_mouse_pressed(button),
+        }
+    }
+
+    pub fn binding_just_pressed(&self, binding: InputBinding) -> bool {
+        match binding {
+            InputBinding::Key(key) => self.just_pressed(key),
+            InputBinding::Mouse(button) => self.mouse_just_pressed(button),
+        }
+    }
+
+    pub fn binding_just_released(&self, binding: InputBinding) -> bool {
+        match binding {
+            InputBinding::Key(key) => self.just_released(key),
+            InputBinding::Mouse(button) => self.mouse_just_released(button),
+        }
+    }
+
+    // Actions, unknown action names are never pressed
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.input_map
+            .get_action_bindings(action)
+            .iter()
+            .any(|binding| self.is_binding_pressed(*binding))
+    }
+
+    pub fn action_just_pressed(&self, action: &str) -> bool {
+        self.input_map
+            .get_action_bindings(action)
+            .iter()
+            .any(|binding| self.binding_just_pressed(*binding))
+    }
+
+    pub fn action_just_released(&self, action: &str) -> bool {
+        self.input_map
+            .get_action_bindings(action)
+            .iter()
+            .any(|binding| self.binding_just_released(*binding))
+    }
+
+    // Sum of all bindings of the axis, clamped to [-1, 1]
+    pub fn get_axis(&self, axis: &str) -> f32 {
+        let mut value: f32 = 0.0;
+        for binding in self.input_map.get_axis_bindings(axis) {
+            if self.is_binding_pressed(binding.positive) {
+                value += 1.0;
+            }
+            if self.is_binding_pressed(binding.negative) {
+                value -= 1.0;
+            }
+        }
+        value.clamp(-1.0, 1.0)
+    }
+}
@@ -1,9 +1,17 @@
 pub mod app;
+pub mod config;
 pub mod ecs;
 pub mod engine;
 pub mod gpu;
+pub mod input;
 pub mod model_push_constant;
+pub mod prefab;
 pub mod scene;
+pub mod scene_file;
+pub mod scene_preload;
+pub mod scene_stack;
+pub mod time;
 pub mod utils;
 
+pub use config::EngineConfig;
 pub use engine::GameEngine;
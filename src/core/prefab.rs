@@ -0,0 +1,158 @@
+use nalgebra::Vector4;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        ecs::{
+            components::{
+                Material3D, PointLight3D, Structure3D, Transform3D,
+                directional_light_3d::DirectionalLight3D, spot_light_3d::SpotLight3D,
+            },
+            entities::game_object::GameObject,
+        },
+        scene::Scene,
+        scene_file::EntityData,
+    },
+    vulkan_backend::backend::VBackend,
+};
+
+// A subtree of entities as written in a prefab file. The first entity is the
+// root of every instance, parents are indices into `entities` like in scene
+// files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefabData {
+    pub entities: Vec<EntityData>,
+}
+
+impl PrefabData {
+    pub fn from_file(path: &str) -> Self {
+        let contents = std::fs::read_to_string(path).expect("failed to read prefab file");
+        ron::from_str(&contents).expect("failed to parse prefab file")
+    }
+
+    pub fn save_to_file(&self, path: &str) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("failed to serialize prefab");
+        std::fs::write(path, contents).expect("failed to write prefab file");
+    }
+}
+
+// A prefab with its meshes and textures loaded, created by
+// `GameEngine::load_prefab`. Every instance shares the same models and
+// materials.
+pub struct Prefab {
+    entities: Vec<PrefabEntity>,
+}
+
+struct PrefabEntity {
+    data: EntityData,
+    structure: Option<Structure3D>,
+    material: Option<Material3D>,
+}
+
+// Per-instance changes applied on top of the prefab
+#[derive(Default)]
+pub struct PrefabOverrides {
+    // Name of the root entity
+    pub name: Option<String>,
+    // Transform of the root entity, the rest of the subtree follows it
+    pub transform: Option<Transform3D>,
+    // Color of every light in the instance
+    pub light_color: Option<Vector4<f32>>,
+}
+
+impl Prefab {
+    pub(crate) fn new() -> Self {
+        Self { entities: vec![] }
+    }
+
+    pub(crate) fn push(
+        &mut self,
+        data: EntityData,
+        structure: Option<Structure3D>,
+        material: Option<Material3D>,
+    ) {
+        self.entities.push(PrefabEntity {
+            data,
+            structure,
+            material,
+        });
+    }
+
+    pub fn get_entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    // Spawns every entity of the prefab into the scene, in file order
+    pub(crate) fn instantiate(
+        &self,
+        v_backend: &VBackend,
+        scene: &mut Scene,
+        overrides: &PrefabOverrides,
+    ) -> Vec<GameObject> {
+        let entities: Vec<GameObject> = self
+            .entities
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| match &overrides.name {
+                Some(name) if index == 0 => scene.spawn(name),
+                _ => scene.spawn(&entity.data.name),
+            })
+            .collect();
+
+        for (index, (entity, prefab_entity)) in
+            entities.iter().zip(self.entities.iter()).enumerate()
+        {
+            let data = &prefab_entity.data;
+            let transform = match &overrides.transform {
+                Some(transform) if index == 0 => Some(transform.clone()),
+                _ => data
+                    .transform
+                    .as_ref()
+                    .map(|transform| transform.to_transform_3d()),
+            };
+            if let Some(transform) = transform {
+                scene.add_transform_3d_component(entity, transform);
+            }
+            if let Some(camera) = &data.camera {
+                scene.add_camera_3d_component(entity, camera.to_camera_3d());
+            }
+            if let Some(light) = &data.point_light {
+                let color = overrides.light_color.unwrap_or(light.get_color());
+                scene.add_point_light_3d_component(entity, PointLight3D::new(color));
+            }
+            if let Some(light) = &data.directional_light {
+                let color = overrides.light_color.unwrap_or(light.get_color());
+                scene.add_directional_light_3d_component(entity, DirectionalLight3D::new(color));
+            }
+            if let Some(light) = &data.spot_light {
+                let color = overrides.light_color.unwrap_or(light.get_color());
+                scene.add_spot_light_3d_component(entity, SpotLight3D::new(color));
+                if light.shadows {
+                    scene
+                        .shadow_mapping
+                        .add_spot_light(v_backend, *entity.get_id());
+                }
+            }
+            if let Some(structure) = &prefab_entity.structure {
+                scene.add_structure_3d_component(entity, structure.clone());
+            }
+            if let Some(material) = &prefab_entity.material {
+                scene.add_material_3d_component(entity, material.clone());
+            }
+            for tag in &data.tags {
+                scene.add_tag(entity, tag);
+            }
+        }
+
+        for (entity, prefab_entity) in entities.iter().zip(self.entities.iter()) {
+            if let Some(parent) = prefab_entity.data.parent {
+                let parent = entities
+                    .get(parent)
+                    .expect("prefab refers to a parent that does not exist");
+                scene.set_parent(entity, parent);
+            }
+        }
+        entities
+    }
+}
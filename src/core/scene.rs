@@ -2,17 +2,32 @@ use crate::{
     core::{
         ecs::{
             components::{
-                Camera3D, Material3D, PointLight3D, Structure3D, Transform3D,
-                directional_light_3d::DirectionalLight3D, spot_light_3d::SpotLight3D,
+                Camera3D, CameraController, Children, Material3D, Parent, PointLight3D,
+                Structure3D, Tags, Transform3D, directional_light_3d::DirectionalLight3D,
+                spot_light_3d::SpotLight3D,
             },
-            entities::game_object::GameObject,
+            entities::{
+                entity::{Entity, EntityAllocator},
+                game_object::GameObject,
+            },
+            events::{EngineEvent, EntityDespawned, EventBus, EventReader, Events},
+            query::{ComponentAccess, Query, QueryData, QueryFilter, ReadOnlyQueryData},
+            schedule::{
+                ParallelContext, ParallelSystemFn, Schedule, Stage, System, SystemContext,
+                SystemFn, SystemJob, SystemRun,
+            },
+            storage::{ComponentRegistry, SparseSet},
             systems::{
-                camera_3d_compute_transform, camera_3d_handle_cm_event, camera_3d_handle_ki_event,
-                update_transform_3d_matrix,
+                CAMERA_3D_SYSTEM, GLOBAL_UNIFORM_SYSTEM, LIGHT_UNIFORMS_SYSTEM,
+                TRANSFORM_3D_SYSTEM, camera_3d_compute_transform, camera_3d_follow,
+                camera_3d_handle_ki_event, camera_3d_handle_mm_event, camera_3d_handle_mw_event,
+                camera_3d_orbit, camera_3d_path, camera_3d_take_look_input,
+                set_transform_3d_from_matrix, update_transform_3d_matrix,
             },
-            types::{CursorMovedEvent, KeyboardInputEvent},
+            types::{KeyboardInputEvent, MouseMotionEvent, MouseWheelEvent},
         },
         gpu::{
+            deletion_queue::DeletionQueue,
             directional_light_uniform::DirectionalLightUniform,
             global_uniform::GlobalUniform,
             materials_manager::MaterialsManager,
@@ -20,11 +35,11 @@ use crate::{
             scene_render::{DrawableSceneElement, RecordableScene, SceneRenderer},
             shadow_mapping::ShadowMapping,
             spot_light_uniform::SpotLightUniform,
-            texture::ImageTexture,
         },
         model_push_constant::ModelPushConstant,
+        scene_file::SceneData,
     },
-    shared::types::Id,
+    log,
     vulkan_backend::{
         backend::VBackend,
         backend_event::VBackendEvent,
@@ -36,8 +51,10 @@ use crate::{
     },
 };
 use ash::vk;
-use nalgebra::Vector4;
-use std::collections::HashMap;
+use nalgebra::{Matrix4, Vector2, Vector3, Vector4};
+use rayon::prelude::*;
+use std::any::TypeId;
+use std::collections::HashSet;
 use std::mem::size_of;
 
 pub struct Scene {
@@ -48,35 +65,110 @@ pub struct Scene {
     pub lights_set: VDescriptorSet,
 
     // ECS
-    active_camera: Option<Id>,
+    active_camera: Option<Entity>,
     global_uniform: GlobalUniform,
     point_light_uniform: PointLightUniform,
     directional_light_uniform: DirectionalLightUniform,
     spot_light_uniform: SpotLightUniform,
-    entities: Vec<GameObject>,
-    transform_3d_components: HashMap<Id, Transform3D>,
-    camera_3d_components: HashMap<Id, Camera3D>,
-    point_light_3d_components: HashMap<Id, PointLight3D>,
-    directional_light_3d_components: HashMap<Id, DirectionalLight3D>,
-    spot_light_3d_components: HashMap<Id, SpotLight3D>,
-    structure_3d_components: HashMap<Id, Structure3D>,
-    material_3d_components: HashMap<Id, Material3D>,
-
-    // Defaults
-    texture: ImageTexture,
+    entities: EntityAllocator,
+    game_objects: SparseSet<GameObject>,
+    components: ComponentRegistry,
+    schedule: Schedule,
+    events: EventBus,
 
     // Shadow Mapping
     pub shadow_mapping: ShadowMapping,
 
+    // GPU resources of removed components
+    deletion_queue: DeletionQueue,
+
     // Status
     is_extent_dirty: bool,
-    has_point_light_3d_changed: bool,
-    has_directional_light_3d_changed: bool,
-    has_spot_light_3d_changed: bool,
+    // Entities whose parent changed since the last transform pass
+    reparented: Vec<Entity>,
+    // Change ticks at the start of the current frame and of the last passes,
+    // see `ComponentRegistry::get_change_tick`
+    frame_start_tick: u64,
+    transforms_3d_tick: u64,
+    camera_3d_tick: u64,
+    // Camera whose view and projection were uploaded last
+    uploaded_camera: Option<Entity>,
+    point_light_slots: LightSlots,
+    directional_light_slots: LightSlots,
+    spot_light_slots: LightSlots,
 
     // Others
     current_extent: vk::Extent2D,
     ambient_color: Vector4<f32>,
+    is_ambient_color_dirty: bool,
+}
+
+// Which light each slot of a light uniform holds, so only changed slots are
+// uploaded again
+struct LightSlots {
+    entities: Vec<Entity>,
+    // Change tick of the last upload
+    uploaded_tick: u64,
+    // Lights whose world transform was rebuilt since the last upload
+    moved: HashSet<Entity>,
+}
+
+impl LightSlots {
+    fn new() -> Self {
+        Self {
+            entities: vec![],
+            uploaded_tick: 0,
+            moved: HashSet::new(),
+        }
+    }
+
+    fn add_moved<L: 'static>(&mut self, components: &ComponentRegistry, updated: &[Entity]) {
+        self.moved
+            .extend(updated.iter().filter(|id| components.has::<L>(id)));
+    }
+
+    // Lights with a transform fill the slots in storage order, up to 16. A
+    // slot is returned when it got another light, its light changed or moved,
+    // or it is no longer used (None).
+    fn get_changed_slots<L: 'static>(
+        &mut self,
+        components: &ComponentRegistry,
+    ) -> Vec<(usize, Option<Entity>)> {
+        let since = self.uploaded_tick;
+        self.uploaded_tick = components.get_change_tick();
+        let current: Vec<Entity> = components
+            .get_column::<L>()
+            .map(|column| {
+                column
+                    .get_entities()
+                    .iter()
+                    .filter(|id| components.has::<Transform3D>(id))
+                    .take(16)
+                    .copied()
+                    .collect()
+            })
+            .unwrap_or_default();
+        let moved = std::mem::take(&mut self.moved);
+        let is_changed = |id: &Entity| {
+            moved.contains(id)
+                || components
+                    .get_ticks::<L>(id)
+                    .is_some_and(|ticks| ticks.is_changed(since))
+        };
+
+        let mut slots = vec![];
+        for index in 0..current.len().max(self.entities.len()) {
+            match current.get(index) {
+                Some(id) if self.entities.get(index) != Some(id) || is_changed(id) => {
+                    slots.push((index, Some(*id)));
+                }
+                Some(_) => {}
+                None => slots.push((index, None)),
+            }
+        }
+        self.entities = current;
+        slots
+    }
 }
 
 impl Scene {
@@ -96,6 +188,7 @@ impl Scene {
                     },
                 ],
                 max_sets: 3 as u32,
+                flags: vk::DescriptorPoolCreateFlags::empty(),
             },
         );
 
@@ -116,7 +209,6 @@ impl Scene {
         let point_light_uniform = PointLightUniform::new(v_backend);
         let directional_light_uniform = DirectionalLightUniform::new(v_backend);
         let spot_light_uniform = SpotLightUniform::new(v_backend);
-        let texture = ImageTexture::empty(v_backend, vk::Format::R8G8B8A8_SRGB);
         {
             let mut batch = VDescriptorWriteBatch::new();
             global_uniform.queue_descriptor_writes(&global_uniform_set, &mut batch);
@@ -135,88 +227,463 @@ impl Scene {
             point_light_uniform,
             directional_light_uniform,
             spot_light_uniform,
-            entities: Vec::new(),
-            transform_3d_components: HashMap::new(),
-            camera_3d_components: HashMap::new(),
-            point_light_3d_components: HashMap::new(),
-            directional_light_3d_components: HashMap::new(),
-            spot_light_3d_components: HashMap::new(),
-            structure_3d_components: HashMap::new(),
-            material_3d_components: HashMap::new(),
-            texture,
+            entities: EntityAllocator::new(),
+            game_objects: SparseSet::new(),
+            components: ComponentRegistry::new(),
+            schedule: Schedule::new(),
+            events: EventBus::new(),
             shadow_mapping: ShadowMapping::new(),
+            deletion_queue: DeletionQueue::new(),
             is_extent_dirty: false,
-            has_point_light_3d_changed: false,
-            has_directional_light_3d_changed: false,
-            has_spot_light_3d_changed: false,
-            current_extent: v_backend.v_swapchain.image_extent,
+            reparented: Vec::new(),
+            frame_start_tick: 0,
+            transforms_3d_tick: 0,
+            camera_3d_tick: 0,
+            uploaded_camera: None,
+            point_light_slots: LightSlots::new(),
+            directional_light_slots: LightSlots::new(),
+            spot_light_slots: LightSlots::new(),
+            current_extent: v_backend.v_render_target.image_extent(),
             ambient_color: Vector4::new(0.1, 0.1, 0.1, 0.15),
+            is_ambient_color_dirty: false,
         };
 
         scene
             .global_uniform
             .update_ambient_color(v_backend, 0, &scene.ambient_color);
+        scene.add_builtin_systems();
 
         scene
     }
 
     pub fn handle_keyboard_input(&mut self, event: &KeyboardInputEvent) {
         if let Some(active_id) = self.active_camera {
-            if let Some(camera) = self.camera_3d_components.get_mut(&active_id) {
+            if let Some(camera) = self.components.get_mut::<Camera3D>(&active_id) {
                 camera_3d_handle_ki_event(camera, event);
             }
         }
     }
 
-    pub fn handle_cursor_moved(&mut self, event: &CursorMovedEvent) {
+    pub fn handle_mouse_motion(&mut self, event: &MouseMotionEvent) {
         if let Some(active_id) = self.active_camera {
-            if let Some(camera) = self.camera_3d_components.get_mut(&active_id) {
-                camera_3d_handle_cm_event(camera, event);
+            if let Some(camera) = self.components.get_mut::<Camera3D>(&active_id) {
+                camera_3d_handle_mm_event(camera, event);
             }
         }
     }
 
+    pub fn handle_mouse_wheel(&mut self, event: &MouseWheelEvent) {
+        if let Some(active_id) = self.active_camera
+            && let Some(camera) = self.components.get_mut::<Camera3D>(&active_id)
+        {
+            camera_3d_handle_mw_event(camera, event);
+        }
+    }
+
     pub fn handle_backend_event(&mut self, event: &VBackendEvent) {
         match event {
-            VBackendEvent::UpdateFramebuffers(_, v_swapchain) => {
-                self.current_extent = v_swapchain.image_extent;
+            VBackendEvent::UpdateFramebuffers(_, v_render_target) => {
+                self.current_extent = v_render_target.image_extent();
                 self.is_extent_dirty = true;
+                self.events.send(EngineEvent::Resized {
+                    width: self.current_extent.width,
+                    height: self.current_extent.height,
+                });
             }
             _ => (),
         }
     }
 
     pub fn get_transform_3d_component(&mut self, entity: &GameObject) -> &mut Transform3D {
-        self.transform_3d_components
-            .get_mut(entity.get_id())
+        self.get_mut::<Transform3D>(entity)
             .expect("failed to get transform 3d component from entity")
     }
 
-    pub fn add_game_object(&mut self, entity: GameObject) {
-        self.entities.push(entity);
+    pub fn spawn(&mut self, name: &str) -> GameObject {
+        let entity = GameObject::new(self.entities.allocate(), name);
+        self.game_objects.insert(*entity.get_id(), entity.clone());
+        entity
     }
 
-    pub fn add_transform_3d_component(&mut self, entity: &GameObject, transform3d: Transform3D) {
-        self.transform_3d_components
-            .insert(*entity.get_id(), transform3d);
+    // False once the entity was despawned, even if its slot was reused
+    pub fn is_alive(&self, entity: &GameObject) -> bool {
+        self.entities.is_alive(*entity.get_id())
     }
 
-    pub fn add_camera_3d_component(&mut self, entity: &GameObject, camera: Camera3D) {
+    pub fn get_game_object(&self, id: &Entity) -> Option<&GameObject> {
+        self.game_objects.get(id)
+    }
+
+    // In spawn order, as long as nothing was despawned
+    pub fn get_game_objects(&self) -> impl Iterator<Item = &GameObject> {
+        self.game_objects.iter().map(|(_, entity)| entity)
+    }
+
+    // The entity with the lowest index when several share the name
+    pub fn find_by_name(&self, name: &str) -> Option<GameObject> {
+        self.get_game_objects()
+            .filter(|entity| entity.name == name)
+            .min_by_key(|entity| *entity.get_id())
+            .cloned()
+    }
+
+    // Sorted by entity, so the order doesn't depend on removals
+    pub fn find_all_with_tag(&self, tag: &str) -> Vec<GameObject> {
+        let mut entities: Vec<GameObject> = self
+            .components
+            .iter::<Tags>()
+            .filter(|(_, tags)| tags.has(tag))
+            .filter_map(|(id, _)| self.game_objects.get(id).cloned())
+            .collect();
+        entities.sort_by_key(|entity| *entity.get_id());
+        entities
+    }
+
+    pub fn add_tag(&mut self, entity: &GameObject, tag: &str) {
+        match self.get_mut::<Tags>(entity) {
+            Some(tags) => tags.add(tag),
+            None => {
+                self.insert(entity, Tags::new(&[tag]));
+            }
+        }
+    }
+
+    pub fn remove_tag(&mut self, entity: &GameObject, tag: &str) {
+        if let Some(tags) = self.get_mut::<Tags>(entity) {
+            tags.remove(tag);
+        }
+    }
+
+    // Handles cloned before keep the old name, the scene's copy is the one
+    // `find_by_name` looks at
+    pub fn rename(&mut self, entity: &GameObject, name: &str) {
+        if let Some(game_object) = self.game_objects.get_mut(entity.get_id()) {
+            game_object.name = name.into();
+        }
+    }
+
+    // Logs every entity by name, indented under its parent
+    pub fn log_hierarchy(&self) {
+        let mut roots: Vec<Entity> = self
+            .game_objects
+            .get_entities()
+            .iter()
+            .filter(|id| !self.components.has::<Parent>(id))
+            .copied()
+            .collect();
+        roots.sort();
+
+        let mut stack: Vec<(Entity, usize)> = roots.into_iter().rev().map(|id| (id, 0)).collect();
+        while let Some((id, depth)) = stack.pop() {
+            if let Some(entity) = self.game_objects.get(&id) {
+                log!(format!("{}{:?}", "  ".repeat(depth), entity));
+            }
+            if let Some(children) = self.components.get::<Children>(&id) {
+                stack.extend(children.ids.iter().rev().map(|child| (*child, depth + 1)));
+            }
+        }
+    }
+
+    // Attaches a component of any type, replacing the one of the same type
+    // the entity already had
+    // Panics for despawned entities, whose slot may belong to another one now
+    pub fn insert<T: 'static>(&mut self, entity: &GameObject, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            panic!(
+                "cannot insert a component into {}, it was despawned",
+                entity.name
+            );
+        }
         let id = *entity.get_id();
-        self.camera_3d_components.insert(id, camera);
-        if self.active_camera.is_none() {
+        let previous = self.components.insert(id, component);
+        if TypeId::of::<T>() == TypeId::of::<Camera3D>() && self.active_camera.is_none() {
             self.active_camera = Some(id);
         }
+        previous
+    }
+
+    pub fn get<T: 'static>(&self, entity: &GameObject) -> Option<&T> {
+        self.components.get::<T>(entity.get_id())
+    }
+
+    // Marks the component as changed, see `Changed`
+    pub fn get_mut<T: 'static>(&mut self, entity: &GameObject) -> Option<&mut T> {
+        self.components.get_mut::<T>(entity.get_id())
+    }
+
+    // Counts as a change for `Changed` without borrowing the component.
+    // Returns false when the entity has no such component
+    pub fn mark_changed<T: 'static>(&mut self, entity: &GameObject) -> bool {
+        self.components.mark_changed::<T>(entity.get_id())
+    }
+
+    // GPU resources owned by the removed component are left to the caller,
+    // see `queue_gpu_deletion`
+    pub fn remove<T: 'static>(&mut self, entity: &GameObject) -> Option<T> {
+        let removed = self.components.remove::<T>(entity.get_id());
+        if removed.is_some() {
+            self.on_component_removed(TypeId::of::<T>(), entity.get_id());
+        }
+        removed
+    }
+
+    pub fn has<T: 'static>(&self, entity: &GameObject) -> bool {
+        self.components.has::<T>(entity.get_id())
+    }
+
+    // Entities that lost a `T` since the running system last ran, or outside
+    // of systems over the last frame. Despawns count as well.
+    pub fn get_removed<T: 'static>(&self) -> Vec<Entity> {
+        self.components.get_removed::<T>()
+    }
+
+    // Removes the entity and all its components. Its GPU resources are
+    // destroyed once no frame in flight can use them anymore.
+    // Children are detached and keep their place in the world
+    pub fn despawn(&mut self, entity: &GameObject) {
+        self.despawn_id(*entity.get_id());
+    }
+
+    // Despawns the entity together with all its descendants
+    pub fn despawn_recursive(&mut self, entity: &GameObject) {
+        for id in self.get_subtree(*entity.get_id()) {
+            self.despawn_id(id);
+        }
+    }
+
+    fn despawn_id(&mut self, id: Entity) {
+        if !self.entities.free(id) {
+            return;
+        }
+        if let Some(game_object) = self.game_objects.remove(&id) {
+            self.events.send(EntityDespawned {
+                entity: id,
+                name: game_object.name,
+            });
+        }
+
+        let type_ids = self.components.get_component_types(&id);
+        self.components.remove_entity(&id);
+        for type_id in type_ids {
+            self.on_component_removed(type_id, &id);
+        }
+    }
+
+    // The child's transform becomes relative to the parent's. Panics if the
+    // parent is the child itself or one of its descendants.
+    pub fn set_parent(&mut self, child: &GameObject, parent: &GameObject) {
+        if !self.is_alive(child) || !self.is_alive(parent) {
+            panic!(
+                "cannot parent {} to {}, one of them was despawned",
+                child.name, parent.name
+            );
+        }
+        let child_id = *child.get_id();
+        let parent_id = *parent.get_id();
+        if child_id == parent_id || self.get_ancestors(parent_id).contains(&child_id) {
+            panic!(
+                "{} cannot be parented to {}, which is part of its own subtree",
+                child.name, parent.name
+            );
+        }
+
+        self.remove_parent(child);
+        self.components.insert(child_id, Parent { id: parent_id });
+        match self.components.get_mut::<Children>(&parent_id) {
+            Some(children) => children.ids.push(child_id),
+            None => {
+                self.components.insert(
+                    parent_id,
+                    Children {
+                        ids: vec![child_id],
+                    },
+                );
+            }
+        }
+        self.reparented.push(child_id);
+    }
+
+    // The child's transform becomes relative to the world again
+    pub fn remove_parent(&mut self, child: &GameObject) {
+        self.remove::<Parent>(child);
+    }
+
+    pub fn get_parent(&self, entity: &GameObject) -> Option<&Entity> {
+        self.components
+            .get::<Parent>(entity.get_id())
+            .map(|parent| parent.get_id())
+    }
+
+    pub fn get_children(&self, entity: &GameObject) -> &[Entity] {
+        self.components
+            .get::<Children>(entity.get_id())
+            .map_or(&[], |children| children.get_ids())
+    }
+
+    // Nearest first
+    fn get_ancestors(&self, id: Entity) -> Vec<Entity> {
+        let mut ancestors = vec![];
+        let mut current = id;
+        while let Some(parent) = self.components.get::<Parent>(&current) {
+            current = parent.id;
+            ancestors.push(current);
+        }
+        ancestors
+    }
+
+    // The entity followed by all its descendants, parents before children
+    fn get_subtree(&self, id: Entity) -> Vec<Entity> {
+        let mut subtree = vec![id];
+        let mut index = 0;
+        while index < subtree.len() {
+            if let Some(children) = self.components.get::<Children>(&subtree[index]) {
+                subtree.extend(children.ids.iter().copied());
+            }
+            index += 1;
+        }
+        subtree
+    }
+
+    // World transform of the nearest ancestor with a transform
+    fn get_parent_world_transform(&self, id: Entity) -> Matrix4<f32> {
+        self.get_ancestors(id)
+            .iter()
+            .find_map(|ancestor| self.components.get::<Transform3D>(ancestor))
+            .map_or(Matrix4::identity(), |t| t.world_transform)
+    }
+
+    // Readable by every system this frame and the next, see `Events`
+    pub fn send_event<T: 'static>(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    // Events of the type not yet seen by this reader
+    pub fn read_events<'e, T: 'static>(
+        &'e self,
+        reader: &mut EventReader<T>,
+    ) -> impl Iterator<Item = &'e T> + use<'e, T> {
+        self.events.read(reader)
+    }
+
+    // All buffered events of the type, for code without a reader of its own
+    pub fn get_events<T: 'static>(&self) -> Option<&Events<T>> {
+        self.events.get_events::<T>()
+    }
+
+    // Destroys GPU resources after the frames in flight that may use them
+    pub fn queue_gpu_deletion(&mut self, deletion: impl FnOnce(&VBackend) + 'static) {
+        self.deletion_queue.push(deletion);
+    }
+
+    // The shadow map is destroyed after the frames in flight that may sample it
+    pub fn queue_spot_light_shadow_removal(&mut self, entity: &GameObject) {
+        self.shadow_mapping
+            .queue_spot_light_removal(&mut self.deletion_queue, entity.get_id());
+    }
+
+    // Called by the engine after every submitted frame
+    pub fn advance_deletion_queue(&mut self, v_backend: &VBackend) {
+        self.deletion_queue.advance(v_backend);
+    }
+
+    fn on_component_removed(&mut self, type_id: TypeId, id: &Entity) {
+        if type_id == TypeId::of::<Transform3D>() {
+            // Children now inherit the grandparent's transform
+            self.reparented.push(*id);
+        } else if type_id == TypeId::of::<Parent>() {
+            if let Some(children) = self
+                .components
+                .iter_mut::<Children>()
+                .map(|(_, children)| children)
+                .find(|children| children.ids.contains(id))
+            {
+                children.ids.retain(|child| child != id);
+            }
+            self.reparented.push(*id);
+        } else if type_id == TypeId::of::<Children>() {
+            let orphans: Vec<Entity> = self
+                .components
+                .iter::<Parent>()
+                .filter(|(_, parent)| parent.id == *id)
+                .map(|(child, _)| *child)
+                .collect();
+            // Their local transform is rebuilt from the world one, so they
+            // stay where they are
+            for orphan in orphans {
+                if let Some(t) = self.components.get_mut::<Transform3D>(&orphan) {
+                    let world = t.world_transform;
+                    set_transform_3d_from_matrix(t, &world);
+                }
+                self.components.remove::<Parent>(&orphan);
+                self.reparented.push(orphan);
+            }
+        } else if type_id == TypeId::of::<Camera3D>() && self.active_camera == Some(*id) {
+            // Fall back to any camera left in the scene
+            self.active_camera = self
+                .components
+                .iter::<Camera3D>()
+                .map(|(camera_id, _)| *camera_id)
+                .next();
+            self.is_extent_dirty = true;
+        } else if type_id == TypeId::of::<SpotLight3D>() {
+            self.shadow_mapping
+                .queue_spot_light_removal(&mut self.deletion_queue, id);
+        }
+    }
+
+    // Iterates the components of every entity that has all the required ones,
+    // e.g. `scene.query::<(&Transform3D, &mut PointLight3D)>()`
+    pub fn query<D: QueryData>(&mut self) -> Query<'_, D> {
+        self.query_filtered::<D, ()>()
+    }
+
+    // Same as `query`, restricted by `With<T>` / `Without<T>` or
+    // `Added<T>` / `Changed<T>` filters
+    pub fn query_filtered<D: QueryData, F: QueryFilter>(&mut self) -> Query<'_, D> {
+        self.components.query_filtered::<D, F>()
+    }
+
+    pub fn query_ref<D: ReadOnlyQueryData>(&self) -> Query<'_, D> {
+        self.components.query_ref::<D>()
+    }
+
+    pub fn query_ref_filtered<D: ReadOnlyQueryData, F: QueryFilter>(&self) -> Query<'_, D> {
+        self.components.query_ref_filtered::<D, F>()
+    }
+
+    pub fn add_transform_3d_component(&mut self, entity: &GameObject, transform3d: Transform3D) {
+        self.insert(entity, transform3d);
+    }
+
+    pub fn add_camera_3d_component(&mut self, entity: &GameObject, camera: Camera3D) {
+        self.insert(entity, camera);
     }
 
     pub fn set_active_camera(&mut self, entity: &GameObject) {
         self.active_camera = Some(*entity.get_id());
     }
 
+    pub fn get_active_camera(&self) -> Option<&Entity> {
+        self.active_camera.as_ref()
+    }
+
+    pub fn get_ambient_color(&self) -> Vector4<f32> {
+        self.ambient_color
+    }
+
+    pub fn set_ambient_color(&mut self, ambient_color: Vector4<f32>) {
+        self.ambient_color = ambient_color;
+        self.is_ambient_color_dirty = true;
+    }
+
+    // Meshes and textures are saved as the paths they were loaded from, see
+    // `GameEngine::load_scene`
+    pub fn save_to_file(&self, path: &str) {
+        SceneData::from_scene(self).save_to_file(path);
+    }
+
     pub fn add_point_light_3d_component(&mut self, entity: &GameObject, point_light: PointLight3D) {
-        let id = *entity.get_id();
-        self.point_light_3d_components.insert(id, point_light);
-        self.has_point_light_3d_changed = true;
+        self.insert(entity, point_light);
     }
 
     pub fn add_directional_light_3d_component(
@@ -224,71 +691,441 @@ impl Scene {
         entity: &GameObject,
         directional_light: DirectionalLight3D,
     ) {
-        let id = *entity.get_id();
-        self.directional_light_3d_components
-            .insert(id, directional_light);
-        self.has_directional_light_3d_changed = true;
+        self.insert(entity, directional_light);
     }
 
     pub fn add_spot_light_3d_component(&mut self, entity: &GameObject, spot_light: SpotLight3D) {
-        let id = *entity.get_id();
-        self.spot_light_3d_components.insert(id, spot_light);
-        self.has_spot_light_3d_changed = true;
+        self.insert(entity, spot_light);
     }
 
     pub fn add_structure_3d_component(&mut self, entity: &GameObject, structure: Structure3D) {
-        let id = *entity.get_id();
-        self.structure_3d_components.insert(id, structure);
+        self.insert(entity, structure);
     }
 
     pub fn add_material_3d_component(&mut self, entity: &GameObject, material: Material3D) {
-        let id = *entity.get_id();
-        self.material_3d_components.insert(id, material);
+        self.insert(entity, material);
     }
 
-    pub fn mark_directional_light_3d_dirty(&mut self) {
-        self.has_directional_light_3d_changed = true;
+    pub fn remove_transform_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<Transform3D>(entity);
     }
 
-    pub fn pre_render(&mut self, v_backend: &VBackend, dt: f32) {
-        self.update_global_uniform(v_backend, dt);
+    pub fn remove_camera_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<Camera3D>(entity);
+    }
 
-        // Update dirty Transforms 3D and flag light uniform updates if any light moved
-        for (entity_id, t) in self.transform_3d_components.iter_mut() {
-            if t.dirty {
-                update_transform_3d_matrix(t);
-                if self.point_light_3d_components.contains_key(entity_id) {
-                    self.has_point_light_3d_changed = true;
+    pub fn add_camera_controller_component(
+        &mut self,
+        entity: &GameObject,
+        controller: CameraController,
+    ) {
+        self.insert(entity, controller);
+    }
+
+    pub fn remove_camera_controller_component(&mut self, entity: &GameObject) {
+        self.remove::<CameraController>(entity);
+    }
+
+    pub fn remove_point_light_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<PointLight3D>(entity);
+    }
+
+    pub fn remove_directional_light_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<DirectionalLight3D>(entity);
+    }
+
+    pub fn remove_spot_light_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<SpotLight3D>(entity);
+    }
+
+    pub fn remove_structure_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<Structure3D>(entity);
+    }
+
+    pub fn remove_material_3d_component(&mut self, entity: &GameObject) {
+        self.remove::<Material3D>(entity);
+    }
+
+    // Runs every stage of the schedule, see `Stage`
+    pub fn pre_render(&mut self, ctx: &SystemContext) {
+        // Events from two frames ago are dropped here
+        self.events.update();
+        // Every system that ran last frame has seen these removals
+        self.components.clear_removed_before(self.frame_start_tick);
+        self.frame_start_tick = self.components.get_change_tick();
+        for stage in Stage::ALL {
+            self.run_stage(stage, ctx);
+        }
+        // Changes made until the next frame get a tick no system has seen.
+        // Outside of systems, changes count from the end of the frame before.
+        self.components.advance_change_tick();
+        self.components.set_last_run_tick(self.frame_start_tick - 1);
+    }
+
+    // Every system sees the changes made since its own previous run
+    pub fn run_stage(&mut self, stage: Stage, ctx: &SystemContext) {
+        let outside_tick = self.components.get_last_run_tick();
+        for batch in self.schedule.get_stage_batches(stage).to_vec() {
+            let mut parallel = vec![];
+            for name in batch {
+                match self.schedule.take_run(&name) {
+                    Some(SystemRun::Exclusive(run)) => self.run_exclusive_system(&name, run, ctx),
+                    Some(SystemRun::Parallel(run)) => parallel.push((name, run)),
+                    None => {}
                 }
             }
+
+            if parallel.is_empty() {
+                continue;
+            }
+            // The whole batch shares one tick
+            let tick = self.components.advance_change_tick();
+            self.run_parallel_systems(&parallel, ctx);
+            for (name, run) in parallel {
+                self.schedule.set_last_run_tick(&name, tick);
+                self.schedule.restore_run(&name, SystemRun::Parallel(run));
+            }
+        }
+        self.components.set_last_run_tick(outside_tick);
+    }
+
+    fn run_exclusive_system(&mut self, name: &str, mut run: Box<SystemFn>, ctx: &SystemContext) {
+        let since = self.schedule.get_last_run_tick(name);
+        self.components.set_last_run_tick(since);
+        let tick = self.components.advance_change_tick();
+        run(self, ctx);
+        self.schedule.set_last_run_tick(name, tick);
+        self.schedule.restore_run(name, SystemRun::Exclusive(run));
+    }
+
+    // For scenes drawn under an overlay that pauses them. Their own systems
+    // and the camera stand still, but changes made from outside, e.g. a
+    // resize, still reach what they are drawn with.
+    pub fn pre_render_paused(&mut self, ctx: &SystemContext) {
+        let outside_tick = self.components.get_last_run_tick();
+        for name in [
+            TRANSFORM_3D_SYSTEM,
+            GLOBAL_UNIFORM_SYSTEM,
+            LIGHT_UNIFORMS_SYSTEM,
+        ] {
+            match self.schedule.take_run(name) {
+                Some(SystemRun::Exclusive(run)) => self.run_exclusive_system(name, run, ctx),
+                Some(run) => self.schedule.restore_run(name, run),
+                None => {}
+            }
+        }
+        self.components.advance_change_tick();
+        self.components.set_last_run_tick(outside_tick);
+    }
+
+    // Runs one batch on the thread pool. The batch never writes a component
+    // another of its systems touches, so the result doesn't depend on timing.
+    fn run_parallel_systems(
+        &mut self,
+        systems: &[(String, Box<dyn ParallelSystemFn>)],
+        ctx: &SystemContext,
+    ) {
+        let accesses: Vec<&[ComponentAccess]> =
+            systems.iter().map(|(_, run)| run.get_access()).collect();
+        let ids: Vec<Vec<Entity>> = systems
+            .iter()
+            .map(|(name, run)| {
+                let since = self.schedule.get_last_run_tick(name);
+                self.components.set_last_run_tick(since);
+                run.get_matching_ids(&self.components)
+            })
+            .collect();
+
+        let parallel_ctx = ParallelContext {
+            input: ctx.input,
+            dt: ctx.dt,
+        };
+        {
+            let mut columns = self.components.split_columns(&accesses);
+            let mut jobs: Vec<SystemJob> = systems
+                .iter()
+                .zip(columns.iter_mut())
+                .zip(ids)
+                .map(|(((_, run), columns), ids)| run.make_job(columns, ids))
+                .collect();
+            // A lone system isn't worth handing to the pool
+            if jobs.len() == 1 {
+                (jobs.remove(0))(&parallel_ctx);
+            } else {
+                rayon::scope(|scope| {
+                    for job in jobs {
+                        let parallel_ctx = &parallel_ctx;
+                        scope.spawn(move |_| job(parallel_ctx));
+                    }
+                });
+            }
         }
+    }
+
+    pub fn add_system(&mut self, system: System) {
+        self.schedule.add_system(system);
+    }
 
-        // Update point light uniform if needed
-        self.update_point_light_uniform(v_backend);
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        self.schedule.remove_system(name)
+    }
 
-        // Update directional light uniform if needed
-        self.update_directional_light_uniform(v_backend);
+    pub fn has_system(&self, name: &str) -> bool {
+        self.schedule.has_system(name)
+    }
 
-        // Update spot light uniform if needed
-        self.update_spot_light_uniform(v_backend);
+    fn add_builtin_systems(&mut self) {
+        self.add_system(System::new(
+            CAMERA_3D_SYSTEM,
+            Stage::Update,
+            |scene, ctx| scene.update_active_camera_3d(ctx.dt),
+        ));
+        self.add_system(System::new(
+            TRANSFORM_3D_SYSTEM,
+            Stage::PostUpdate,
+            |scene, _| scene.update_transforms_3d(),
+        ));
+        self.add_system(System::new(
+            GLOBAL_UNIFORM_SYSTEM,
+            Stage::PreRender,
+            |scene, ctx| scene.update_global_uniform(ctx.v_backend),
+        ));
+        self.add_system(System::new(
+            LIGHT_UNIFORMS_SYSTEM,
+            Stage::PreRender,
+            |scene, ctx| {
+                scene.update_point_light_uniform(ctx.v_backend);
+                scene.update_directional_light_uniform(ctx.v_backend);
+                scene.update_spot_light_uniform(ctx.v_backend);
+            },
+        ));
     }
 
-    pub fn update_global_uniform(&mut self, v_backend: &VBackend, dt: f32) {
-        if let Some(active_camera_id) = self.active_camera {
+    // Moves the active camera by its controller and the input queued on it
+    pub fn update_active_camera_3d(&mut self, dt: f32) {
+        let Some(active_camera_id) = self.active_camera else {
+            return;
+        };
+        let target = match self.components.get::<CameraController>(&active_camera_id) {
+            None | Some(CameraController::Fly) => {
+                self.fly_active_camera_3d(active_camera_id, dt);
+                return;
+            }
+            Some(controller) => controller.get_target(),
+        };
+        // Checked first, borrowing the camera mutably marks it as changed.
+        // Held keys stay queued and don't count, controllers only look.
+        let has_input = self
+            .components
+            .get::<Camera3D>(&active_camera_id)
+            .is_some_and(|camera_3d| {
+                !camera_3d.mm_events.is_empty() || !camera_3d.mw_events.is_empty()
+            });
+        let (look, zoom) = if has_input {
             let camera_3d = self
-                .camera_3d_components
-                .get_mut(&active_camera_id)
-                .expect("failed to get active camera from id");
-            if self.is_extent_dirty
-                || camera_3d.ki_events.len() > 0
-                || camera_3d.cm_events.len() > 0
-                || camera_3d.transform.dirty
-            {
-                camera_3d_compute_transform(camera_3d, dt);
-                let (view, projection) = camera_3d
-                    .transform
-                    .get_transform_3d_view_projection(self.current_extent);
-                camera_3d.transform.dirty = false;
+                .components
+                .get_mut::<Camera3D>(&active_camera_id)
+                .unwrap();
+            camera_3d_take_look_input(camera_3d)
+        } else {
+            (Vector2::zeros(), 0.0)
+        };
+        // Targets that were despawned leave the camera where it is
+        let target_world = match target {
+            Some(target) => match self.get_current_world_transform(target) {
+                Some(target_world) => target_world,
+                None => return,
+            },
+            None => Matrix4::identity(),
+        };
+        let Some(current) = self.components.get::<Transform3D>(&active_camera_id) else {
+            return;
+        };
+        let mut transform = current.clone();
+
+        match self
+            .components
+            .get_mut::<CameraController>(&active_camera_id)
+            .unwrap()
+        {
+            CameraController::Orbit(orbit) => {
+                let target = target_world.fixed_view::<3, 1>(0, 3).into();
+                camera_3d_orbit(orbit, &mut transform, target, look, zoom);
+            }
+            CameraController::Follow(follow) => {
+                camera_3d_follow(follow, &mut transform, &target_world, dt)
+            }
+            CameraController::Path(path) => camera_3d_path(path, &mut transform, dt),
+            // Flying reads the keys as well, it was stepped by
+            // `fly_active_camera_3d` above
+            CameraController::Fly => return,
+        }
+
+        // A camera at rest must not count as changed
+        let current = self
+            .components
+            .get::<Transform3D>(&active_camera_id)
+            .unwrap();
+        if current.position != transform.position || current.rotation != transform.rotation {
+            *self
+                .components
+                .get_mut::<Transform3D>(&active_camera_id)
+                .unwrap() = transform;
+        }
+    }
+
+    fn fly_active_camera_3d(&mut self, active_camera_id: Entity, dt: f32) {
+        // Checked first, borrowing the camera mutably marks it as changed
+        let has_input = self
+            .components
+            .get::<Camera3D>(&active_camera_id)
+            .is_some_and(|camera_3d| {
+                !camera_3d.ki_events.is_empty()
+                    || !camera_3d.mm_events.is_empty()
+                    || !camera_3d.mw_events.is_empty()
+            });
+        if !has_input {
+            return;
+        }
+        let mut transform = self
+            .components
+            .get::<Transform3D>(&active_camera_id)
+            .cloned();
+        let camera_3d = self
+            .components
+            .get_mut::<Camera3D>(&active_camera_id)
+            .unwrap();
+        match &mut transform {
+            Some(transform) => camera_3d_compute_transform(camera_3d, transform, dt),
+            // Nothing to move, the input is dropped
+            None => {
+                camera_3d.ki_events.clear();
+                camera_3d.mm_events.clear();
+                camera_3d.mw_events.clear();
+            }
+        }
+        if let Some(transform) = transform {
+            *self
+                .components
+                .get_mut::<Transform3D>(&active_camera_id)
+                .unwrap() = transform;
+        }
+    }
+
+    // Built from the local transform as it is now, so moves made earlier this
+    // frame count before the transform pass catches up
+    fn get_current_world_transform(&self, id: Entity) -> Option<Matrix4<f32>> {
+        let mut transform = self.components.get::<Transform3D>(&id)?.clone();
+        update_transform_3d_matrix(&mut transform);
+        Some(self.get_parent_world_transform(id) * transform.cached_transform)
+    }
+
+    // Rebuilds the local matrices changed since the last pass, then the world
+    // matrices of the subtrees below them. Both are derived data and written
+    // untracked; the lights and camera below a moved entity are handed to
+    // their uploads instead.
+    pub fn update_transforms_3d(&mut self) {
+        let since = self.transforms_3d_tick;
+        self.transforms_3d_tick = self.components.get_change_tick();
+        let mut moved: HashSet<Entity> = match self.components.get_column_mut::<Transform3D>() {
+            Some(transforms) => transforms
+                .par_iter_mut_untracked()
+                .filter(|(_, _, ticks)| ticks.is_changed(since))
+                .map(|(id, t, _)| {
+                    update_transform_3d_matrix(t);
+                    *id
+                })
+                .collect(),
+            None => HashSet::new(),
+        };
+        moved.extend(self.reparented.drain(..));
+        if moved.is_empty() {
+            return;
+        }
+
+        // A subtree is walked once, from its topmost moved entity
+        let roots: Vec<Entity> = moved
+            .iter()
+            .filter(|id| {
+                !self
+                    .get_ancestors(**id)
+                    .iter()
+                    .any(|ancestor| moved.contains(ancestor))
+            })
+            .copied()
+            .collect();
+        let mut updated = vec![];
+        for root in roots {
+            let parent_world = self.get_parent_world_transform(root);
+            self.propagate_world_transform(root, parent_world, &mut updated);
+        }
+
+        self.point_light_slots
+            .add_moved::<PointLight3D>(&self.components, &updated);
+        self.directional_light_slots
+            .add_moved::<DirectionalLight3D>(&self.components, &updated);
+        self.spot_light_slots
+            .add_moved::<SpotLight3D>(&self.components, &updated);
+        // The camera's view is its world transform, or its parent's
+        if let Some(active_camera_id) = self.active_camera
+            && updated.contains(&active_camera_id)
+        {
+            self.components.mark_changed::<Camera3D>(&active_camera_id);
+        }
+    }
+
+    fn propagate_world_transform(
+        &mut self,
+        root: Entity,
+        parent_world: Matrix4<f32>,
+        updated: &mut Vec<Entity>,
+    ) {
+        let mut stack = vec![(root, parent_world)];
+        while let Some((id, parent_world)) = stack.pop() {
+            // Entities without a transform pass their parent's on
+            let world = match self.components.get_mut_untracked::<Transform3D>(&id) {
+                Some(t) => {
+                    t.world_transform = parent_world * t.cached_transform;
+                    t.world_transform
+                }
+                None => parent_world,
+            };
+            if let Some(children) = self.components.get::<Children>(&id) {
+                stack.extend(children.ids.iter().map(|child| (*child, world)));
+            }
+            updated.push(id);
+        }
+    }
+
+    // Uploads the ambient color when it was set, and view and projection when
+    // the camera changed or was switched, or the extent changed
+    pub fn update_global_uniform(&mut self, v_backend: &VBackend) {
+        if self.is_ambient_color_dirty {
+            self.is_ambient_color_dirty = false;
+            self.global_uniform
+                .update_ambient_color(v_backend, 0, &self.ambient_color);
+        }
+        let since = self.camera_3d_tick;
+        self.camera_3d_tick = self.components.get_change_tick();
+        if let Some(active_camera_id) = self.active_camera {
+            let is_camera_changed = self.uploaded_camera != Some(active_camera_id)
+                || self
+                    .components
+                    .get_ticks::<Camera3D>(&active_camera_id)
+                    .is_some_and(|ticks| ticks.is_changed(since));
+            if self.is_extent_dirty || is_camera_changed {
+                let camera_world = match self.components.get::<Transform3D>(&active_camera_id) {
+                    Some(transform) => transform.world_transform,
+                    None => self.get_parent_world_transform(active_camera_id),
+                };
+                let camera_3d = self
+                    .components
+                    .get::<Camera3D>(&active_camera_id)
+                    .expect("failed to get active camera from id");
+                let projection = camera_3d.projection.get_matrix(self.current_extent);
+                let view = camera_world.try_inverse().unwrap_or_else(Matrix4::identity);
+                self.uploaded_camera = Some(active_camera_id);
+                self.is_extent_dirty = false;
                 self.global_uniform.update_view(v_backend, 0, &view);
                 self.global_uniform
                     .update_projection(v_backend, 0, &projection);
@@ -296,93 +1133,90 @@ impl Scene {
         }
     }
 
+    // Only the slots whose light or its transform changed are uploaded
     pub fn update_point_light_uniform(&mut self, v_backend: &VBackend) {
-        if self.has_point_light_3d_changed {
-            let mut index: usize = 0;
-            for (entity_id, point_light) in self.point_light_3d_components.iter() {
-                if index >= 16 {
-                    break;
-                }
-
-                if let Some(light_transform) = self.transform_3d_components.get(entity_id) {
-                    let p = light_transform.position;
-                    let point = Vector4::new(p.x, p.y, p.z, 1.0);
-                    self.point_light_uniform
-                        .update(v_backend, index, &point, &point_light.color);
+        for (index, id) in self
+            .point_light_slots
+            .get_changed_slots::<PointLight3D>(&self.components)
+        {
+            let (point, color) = match id {
+                Some(id) => {
+                    let p = self
+                        .components
+                        .get::<Transform3D>(&id)
+                        .unwrap()
+                        .get_world_position();
+                    let color = self.components.get::<PointLight3D>(&id).unwrap().color;
+                    (Vector4::new(p.x, p.y, p.z, 1.0), color)
                 }
-                index += 1;
-            }
-            self.has_point_light_3d_changed = false;
+                // Slots of removed lights must not keep lighting the scene
+                None => (Vector4::zeros(), Vector4::zeros()),
+            };
+            self.point_light_uniform
+                .update(v_backend, index, &point, &color);
         }
     }
 
     pub fn update_directional_light_uniform(&mut self, v_backend: &VBackend) {
-        if self.has_directional_light_3d_changed {
-            let mut index: usize = 0;
-            for (entity_id, directional_light) in self.directional_light_3d_components.iter() {
-                if index >= 16 {
-                    break;
-                }
-
-                if let Some(light_transform) = self.transform_3d_components.get(entity_id) {
-                    let direction_raw = (light_transform.get_rotation3()
-                        * nalgebra::Vector3::new(0.0, 0.0, -1.0))
-                    .to_homogeneous();
-                    let direction =
-                        Vector4::new(direction_raw.x, direction_raw.y, direction_raw.z, 1.0);
-
-                    self.directional_light_uniform.update(
-                        v_backend,
-                        index,
-                        &direction,
-                        &directional_light.color,
-                    );
+        for (index, id) in self
+            .directional_light_slots
+            .get_changed_slots::<DirectionalLight3D>(&self.components)
+        {
+            let (direction, color) = match id {
+                Some(id) => {
+                    let direction_raw = self
+                        .components
+                        .get::<Transform3D>(&id)
+                        .unwrap()
+                        .get_world_direction(Vector3::new(0.0, 0.0, -1.0))
+                        .to_homogeneous();
+                    let color = self
+                        .components
+                        .get::<DirectionalLight3D>(&id)
+                        .unwrap()
+                        .color;
+                    (
+                        Vector4::new(direction_raw.x, direction_raw.y, direction_raw.z, 1.0),
+                        color,
+                    )
                 }
-                index += 1;
-            }
-            self.has_directional_light_3d_changed = false;
+                None => (Vector4::zeros(), Vector4::zeros()),
+            };
+            self.directional_light_uniform
+                .update(v_backend, index, &direction, &color);
         }
     }
 
     pub fn update_spot_light_uniform(&mut self, v_backend: &VBackend) {
-        if self.has_spot_light_3d_changed {
-            let mut index: usize = 0;
-            for (entity_id, spot_light) in self.spot_light_3d_components.iter() {
-                if index >= 16 {
-                    break;
-                }
-
-                if let Some(light_transform) = self.transform_3d_components.get(entity_id) {
-                    let position = Vector4::new(
-                        light_transform.position.x,
-                        light_transform.position.y,
-                        light_transform.position.z,
-                        1.0,
-                    );
-                    self.spot_light_uniform.update(
-                        v_backend,
-                        index,
-                        &position,
-                        &(light_transform.get_rotation3() * nalgebra::Vector3::new(0.0, 0.0, -1.0))
-                            .to_homogeneous(),
-                        &spot_light.color,
-                    );
+        for (index, id) in self
+            .spot_light_slots
+            .get_changed_slots::<SpotLight3D>(&self.components)
+        {
+            let (position, direction, color) = match id {
+                Some(id) => {
+                    let light_transform = self.components.get::<Transform3D>(&id).unwrap();
+                    let p = light_transform.get_world_position();
+                    let direction = light_transform
+                        .get_world_direction(Vector3::new(0.0, 0.0, -1.0))
+                        .to_homogeneous();
+                    let color = self.components.get::<SpotLight3D>(&id).unwrap().color;
+                    (Vector4::new(p.x, p.y, p.z, 1.0), direction, color)
                 }
-                index += 1;
-            }
-            self.has_spot_light_3d_changed = false;
+                None => (Vector4::zeros(), Vector4::zeros(), Vector4::zeros()),
+            };
+            self.spot_light_uniform
+                .update(v_backend, index, &position, &direction, &color);
         }
     }
 
-    pub fn destroy(&self, v_backend: &VBackend) {
+    // Expects the device to be idle
+    pub fn destroy(&mut self, v_backend: &VBackend) {
+        self.deletion_queue.flush(v_backend);
+        self.shadow_mapping.destroy(v_backend);
         self.global_uniform.destroy(v_backend);
         self.point_light_uniform.destroy(v_backend);
         self.directional_light_uniform.destroy(v_backend);
         self.spot_light_uniform.destroy(v_backend);
-        for (_, structure_3d) in self.structure_3d_components.iter() {
-            structure_3d.destroy(v_backend);
-        }
-        self.texture.destroy(v_backend);
         self.default_descriptor_pool.destroy(&v_backend.v_device);
     }
 }
@@ -406,44 +1240,51 @@ impl RecordableScene for Scene {
             );
         }
 
-        for (entity_id, structure_3d) in self.structure_3d_components.iter() {
-            if let Some(transform_3d) = self.transform_3d_components.get(entity_id) {
-                let material_3d_index = match self.material_3d_components.get(entity_id) {
-                    Some(material_3d) => material_3d.manager_index,
-                    None => 0,
-                };
+        for (structure_3d, transform_3d, material_3d) in
+            self.components
+                .query_ref::<(&Structure3D, &Transform3D, Option<&Material3D>)>()
+        {
+            let material_3d_index = match material_3d {
+                Some(material_3d) => material_3d.manager_index,
+                None => 0,
+            };
 
-                unsafe {
-                    v_device.device.cmd_bind_descriptor_sets(
-                        cmd,
-                        vk::PipelineBindPoint::GRAPHICS,
-                        *scene_r.get_pipeline_layout(),
-                        2,
-                        &[materials_m.get_set_at(material_3d_index).set],
-                        &[],
-                    );
-                }
+            unsafe {
+                v_device.device.cmd_bind_descriptor_sets(
+                    cmd,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    *scene_r.get_pipeline_layout(),
+                    2,
+                    &[materials_m.get_set_at(material_3d_index).set],
+                    &[],
+                );
+            }
 
-                let push = ModelPushConstant {
-                    transform: transform_3d.cached_transform,
-                };
-                let data = unsafe {
-                    std::slice::from_raw_parts(
-                        (&push as *const ModelPushConstant) as *const u8,
-                        size_of::<ModelPushConstant>(),
-                    )
-                };
-                unsafe {
-                    v_device.device.cmd_push_constants(
-                        cmd,
-                        *scene_r.get_pipeline_layout(),
-                        vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
-                        0,
-                        data,
-                    );
-                }
-                structure_3d.model.draw(v_device, cmd);
+            let push = ModelPushConstant {
+                transform: transform_3d.world_transform,
+            };
+            let data = unsafe {
+                std::slice::from_raw_parts(
+                    (&push as *const ModelPushConstant) as *const u8,
+                    size_of::<ModelPushConstant>(),
+                )
+            };
+            unsafe {
+                v_device.device.cmd_push_constants(
+                    cmd,
+                    *scene_r.get_pipeline_layout(),
+                    vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                    0,
+                    data,
+                );
             }
+            structure_3d.model.draw(v_device, cmd);
         }
     }
+
+    fn is_reverse_z(&self) -> bool {
+        self.active_camera
+            .and_then(|id| self.components.get::<Camera3D>(&id))
+            .is_some_and(|camera_3d| camera_3d.projection.is_reverse_z())
+    }
 }
@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use nalgebra::{Matrix4, Vector3, Vector4};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{
+        ecs::components::{
+            Camera3D, Material3D, PointLight3D, Projection, Structure3D, Tags, Transform3D,
+            directional_light_3d::DirectionalLight3D, spot_light_3d::SpotLight3D,
+        },
+        scene::Scene,
+    },
+    log,
+};
+
+// Text form of a scene, written by `Scene::save_to_file` and turned back into
+// a scene with GPU resources by `GameEngine::load_scene`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneData {
+    #[serde(default = "default_ambient_color")]
+    pub ambient_color: [f32; 4],
+    // Index into `entities`
+    #[serde(default)]
+    pub active_camera: Option<usize>,
+    pub entities: Vec<EntityData>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntityData {
+    pub name: String,
+    // Index into `SceneData::entities`
+    #[serde(default)]
+    pub parent: Option<usize>,
+    #[serde(default)]
+    pub transform: Option<TransformData>,
+    #[serde(default)]
+    pub camera: Option<CameraData>,
+    #[serde(default)]
+    pub point_light: Option<LightData>,
+    #[serde(default)]
+    pub directional_light: Option<LightData>,
+    #[serde(default)]
+    pub spot_light: Option<SpotLightData>,
+    // Path of the .obj file
+    #[serde(default)]
+    pub mesh: Option<String>,
+    // Path of the image used as texture
+    #[serde(default)]
+    pub texture: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransformData {
+    pub position: [f32; 3],
+    // Euler angles as (roll, pitch, yaw) in radians, like `Transform3D::new`
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "default_scale")]
+    pub scale: [f32; 3],
+}
+
+// The camera's pose is the entity's transform
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CameraData {
+    #[serde(default = "default_projection")]
+    pub projection: ProjectionData,
+    pub speed: f32,
+    pub sensitivity: f32,
+    #[serde(default)]
+    pub invert_y: bool,
+}
+
+// Like `Projection`, angles in radians
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProjectionData {
+    Perspective { fov_y: f32, near: f32, far: f32 },
+    Orthographic { size: f32, near: f32, far: f32 },
+    ReverseZ { fov_y: f32, near: f32 },
+    // Column by column
+    Custom([[f32; 4]; 4]),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightData {
+    // rgb plus intensity in w
+    pub color: [f32; 4],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpotLightData {
+    pub color: [f32; 4],
+    #[serde(default)]
+    pub shadows: bool,
+}
+
+fn default_ambient_color() -> [f32; 4] {
+    [0.1, 0.1, 0.1, 0.15]
+}
+
+fn default_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_projection() -> ProjectionData {
+    ProjectionData::from(&Projection::default())
+}
+
+impl SceneData {
+    pub fn from_file(path: &str) -> Self {
+        let contents = std::fs::read_to_string(path).expect("failed to read scene file");
+        ron::from_str(&contents).expect("failed to parse scene file")
+    }
+
+    pub fn save_to_file(&self, path: &str) {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("failed to serialize scene");
+        std::fs::write(path, contents).expect("failed to write scene file");
+    }
+
+    // Entities are written in slot order, so saving the same scene twice
+    // lists them the same way
+    pub fn from_scene(scene: &Scene) -> Self {
+        let mut game_objects: Vec<_> = scene.get_game_objects().collect();
+        game_objects.sort_by_key(|entity| *entity.get_id());
+        let index_of: HashMap<_, _> = game_objects
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| (*entity.get_id(), index))
+            .collect();
+
+        let entities = game_objects
+            .iter()
+            .map(|entity| {
+                let mesh = scene.get::<Structure3D>(entity).and_then(|structure| {
+                    if structure.asset_path.is_none() {
+                        log!(format!(
+                            "Mesh of {} was not loaded from a file, it is not saved",
+                            entity.name
+                        ));
+                    }
+                    structure.asset_path.clone()
+                });
+                EntityData {
+                    name: entity.name.clone(),
+                    parent: scene
+                        .get_parent(entity)
+                        .and_then(|parent| index_of.get(parent).copied()),
+                    transform: scene.get::<Transform3D>(entity).map(TransformData::from),
+                    camera: scene.get::<Camera3D>(entity).map(|camera| CameraData {
+                        projection: ProjectionData::from(&camera.projection),
+                        speed: camera.speed,
+                        sensitivity: camera.sensitivity,
+                        invert_y: camera.invert_y,
+                    }),
+                    point_light: scene.get::<PointLight3D>(entity).map(|light| LightData {
+                        color: light.color.into(),
+                    }),
+                    directional_light: scene.get::<DirectionalLight3D>(entity).map(|light| {
+                        LightData {
+                            color: light.color.into(),
+                        }
+                    }),
+                    spot_light: scene.get::<SpotLight3D>(entity).map(|light| SpotLightData {
+                        color: light.color.into(),
+                        shadows: scene
+                            .shadow_mapping
+                            .spot_light_maps
+                            .contains_key(entity.get_id()),
+                    }),
+                    mesh,
+                    texture: scene
+                        .get::<Material3D>(entity)
+                        .and_then(|material| material.texture_path.clone()),
+                    tags: scene
+                        .get::<Tags>(entity)
+                        .map_or(vec![], |tags| tags.get_names().to_vec()),
+                }
+            })
+            .collect();
+
+        Self {
+            ambient_color: scene.get_ambient_color().into(),
+            active_camera: scene
+                .get_active_camera()
+                .and_then(|camera| index_of.get(camera).copied()),
+            entities,
+        }
+    }
+}
+
+impl From<&Transform3D> for TransformData {
+    fn from(transform: &Transform3D) -> Self {
+        Self {
+            position: transform.position.into(),
+            rotation: transform.get_euler_angles().into(),
+            scale: transform.scale.into(),
+        }
+    }
+}
+
+impl TransformData {
+    pub fn to_transform_3d(&self) -> Transform3D {
+        Transform3D::new(
+            Vector3::from(self.position),
+            Vector3::from(self.rotation),
+            Vector3::from(self.scale),
+        )
+    }
+}
+
+impl CameraData {
+    pub fn to_camera_3d(&self) -> Camera3D {
+        let mut camera = Camera3D::new(self.projection.to_projection());
+        camera.speed = self.speed;
+        camera.sensitivity = self.sensitivity;
+        camera.invert_y = self.invert_y;
+        camera
+    }
+}
+
+impl From<&Projection> for ProjectionData {
+    fn from(projection: &Projection) -> Self {
+        match *projection {
+            Projection::Perspective { fov_y, near, far } => Self::Perspective { fov_y, near, far },
+            Projection::Orthographic { size, near, far } => Self::Orthographic { size, near, far },
+            Projection::ReverseZ { fov_y, near } => Self::ReverseZ { fov_y, near },
+            Projection::Custom(matrix) => Self::Custom(matrix.into()),
+        }
+    }
+}
+
+impl ProjectionData {
+    pub fn to_projection(&self) -> Projection {
+        match *self {
+            Self::Perspective { fov_y, near, far } => Projection::Perspective { fov_y, near, far },
+            Self::Orthographic { size, near, far } => Projection::Orthographic { size, near, far },
+            Self::ReverseZ { fov_y, near } => Projection::ReverseZ { fov_y, near },
+            Self::Custom(matrix) => Projection::Custom(Matrix4::from(matrix)),
+        }
+    }
+}
+
+impl LightData {
+    pub fn get_color(&self) -> Vector4<f32> {
+        Vector4::from(self.color)
+    }
+}
+
+impl SpotLightData {
+    pub fn get_color(&self) -> Vector4<f32> {
+        Vector4::from(self.color)
+    }
+}
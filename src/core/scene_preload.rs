@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use image::RgbaImage;
+
+use crate::core::{
+    gpu::{model::MeshData, texture::ImageTexture},
+    scene_file::{EntityData, SceneData},
+};
+
+// Meshes and images read from disk ahead of time, keyed by path. Whatever is
+// missing here is read when the scene is built.
+#[derive(Default)]
+pub struct SceneAssets {
+    pub meshes: HashMap<String, MeshData>,
+    pub images: HashMap<String, RgbaImage>,
+}
+
+impl SceneAssets {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Reads every mesh and image the entities refer to, each path once
+    pub fn load(entities: &[EntityData]) -> Self {
+        let mut assets = Self::new();
+        for entity in entities {
+            if let Some(mesh) = &entity.mesh
+                && !assets.meshes.contains_key(mesh)
+            {
+                assets.meshes.insert(mesh.clone(), MeshData::from_obj(mesh));
+            }
+            if let Some(texture) = &entity.texture
+                && !assets.images.contains_key(texture)
+            {
+                assets
+                    .images
+                    .insert(texture.clone(), ImageTexture::read_rgba(texture));
+            }
+        }
+        assets
+    }
+}
+
+// Everything of a scene file that doesn't need the GPU, so it can be loaded on
+// another thread, see `GameEngine::preload_scene`
+pub struct PreloadedScene {
+    pub data: SceneData,
+    pub assets: SceneAssets,
+}
+
+impl PreloadedScene {
+    pub fn load(path: &str) -> Self {
+        let data = SceneData::from_file(path);
+        let assets = SceneAssets::load(&data.entities);
+        Self { data, assets }
+    }
+}
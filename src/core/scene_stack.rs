@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use crate::core::scene::Scene;
+
+// How an overlay treats the scenes below it
+#[derive(Default)]
+pub struct OverlayConfig {
+    // Scenes below stop running their systems, e.g. under a pause menu. They
+    // are still drawn.
+    pub pause_below: bool,
+    // Camera input goes to the overlay instead of the active scene
+    pub capture_input: bool,
+}
+
+struct StackEntry {
+    name: String,
+    overlay: OverlayConfig,
+}
+
+// Every loaded scene by name, and the stack of the ones shown: the active
+// scene at the bottom, overlays above it. Scenes off the stack stay loaded
+// until they are removed.
+#[derive(Default)]
+pub struct SceneStack {
+    scenes: HashMap<String, Scene>,
+    entries: Vec<StackEntry>,
+}
+
+impl SceneStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Returns the scene loaded under the same name before, if any
+    pub fn insert(&mut self, name: &str, scene: Scene) -> Option<Scene> {
+        let previous = self.remove(name);
+        self.scenes.insert(name.into(), scene);
+        previous
+    }
+
+    // Also takes the scene off the stack
+    pub fn remove(&mut self, name: &str) -> Option<Scene> {
+        self.entries.retain(|entry| entry.name != name);
+        self.scenes.remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.scenes.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Scene> {
+        self.scenes.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Scene> {
+        self.scenes.get_mut(name)
+    }
+
+    // Replaces the whole stack, overlays included
+    pub fn set_active(&mut self, name: &str) {
+        assert!(self.contains(name), "no scene loaded under the name {name}");
+        self.entries = vec![StackEntry {
+            name: name.into(),
+            overlay: OverlayConfig::default(),
+        }];
+    }
+
+    pub fn push_overlay(&mut self, name: &str, overlay: OverlayConfig) {
+        assert!(self.contains(name), "no scene loaded under the name {name}");
+        assert!(
+            self.entries.iter().all(|entry| entry.name != name),
+            "scene {name} is already on the stack"
+        );
+        assert!(
+            !self.entries.is_empty(),
+            "overlays need an active scene below them"
+        );
+        self.entries.push(StackEntry {
+            name: name.into(),
+            overlay,
+        });
+    }
+
+    // The active scene itself is never popped
+    pub fn pop_overlay(&mut self) -> Option<String> {
+        if self.entries.len() < 2 {
+            return None;
+        }
+        self.entries.pop().map(|entry| entry.name)
+    }
+
+    pub fn get_active_name(&self) -> Option<&str> {
+        self.entries.first().map(|entry| entry.name.as_str())
+    }
+
+    pub fn get_active(&self) -> Option<&Scene> {
+        self.scenes.get(self.get_active_name()?)
+    }
+
+    pub fn get_active_mut(&mut self) -> Option<&mut Scene> {
+        let name = self.entries.first()?.name.as_str();
+        self.scenes.get_mut(name)
+    }
+
+    // Bottom to top, the order they are drawn in
+    pub fn get_stack_names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    // The scenes on the stack that aren't paused by an overlay above them,
+    // bottom to top
+    pub fn get_running_names(&self) -> Vec<String> {
+        let first_running = self
+            .entries
+            .iter()
+            .rposition(|entry| entry.overlay.pause_below)
+            .unwrap_or(0);
+        self.entries[first_running..]
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    // The scenes drawn under an overlay that pauses them, bottom to top
+    pub fn get_paused_names(&self) -> Vec<String> {
+        let first_running = self
+            .entries
+            .iter()
+            .rposition(|entry| entry.overlay.pause_below)
+            .unwrap_or(0);
+        self.entries[..first_running]
+            .iter()
+            .map(|entry| entry.name.clone())
+            .collect()
+    }
+
+    // The topmost overlay capturing input, or the active scene
+    pub fn get_input_scene_mut(&mut self) -> Option<&mut Scene> {
+        let name = self
+            .entries
+            .iter()
+            .rev()
+            .find(|entry| entry.overlay.capture_input)
+            .or(self.entries.first())?
+            .name
+            .as_str();
+        self.scenes.get_mut(name)
+    }
+
+    pub fn get_stack(&self) -> Vec<&Scene> {
+        self.entries
+            .iter()
+            .filter_map(|entry| self.scenes.get(&entry.name))
+            .collect()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Scene> {
+        self.scenes.values_mut()
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = Scene> + '_ {
+        self.entries.clear();
+        self.scenes.drain().map(|(_, scene)| scene)
+    }
+}
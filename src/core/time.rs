@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+// Accumulates frame time and hands it out in fixed size steps
+pub struct FixedTimestep {
+    pub step: Duration,
+    // Steps run at most per frame, time beyond that is dropped so a long
+    // hitch doesn't make the simulation spiral trying to catch up
+    pub max_steps_per_frame: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new(rate_hz: f32, max_steps_per_frame: u32) -> Self {
+        assert!(rate_hz > 0.0, "fixed timestep rate must be positive");
+        Self {
+            step: Duration::from_secs_f32(1.0 / rate_hz),
+            max_steps_per_frame,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    // Adds `dt` and returns how many steps are due this frame
+    pub fn advance(&mut self, dt: Duration) -> u32 {
+        self.accumulator += dt;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step && steps < self.max_steps_per_frame {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        if self.accumulator >= self.step {
+            let leftover = self.accumulator.as_nanos() % self.step.as_nanos();
+            self.accumulator = Duration::from_nanos(leftover as u64);
+        }
+
+        steps
+    }
+
+    // How far the leftover time is into the next step, in [0, 1)
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::ZERO;
+    }
+}
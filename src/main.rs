@@ -7,7 +7,6 @@ use benzene::{
                 Camera3D, PointLight3D, Structure3D, Transform3D,
                 directional_light_3d::DirectionalLight3D, spot_light_3d::SpotLight3D,
             },
-            entities::game_object::GameObject,
             systems::rotate_transform_3d,
         },
     },
@@ -15,22 +14,22 @@ use benzene::{
 };
 use nalgebra::{Vector3, Vector4};
 
-pub struct GameState {
-    pub camera: GameObject,
-    pub spot_light: GameObject,
-    pub plane: GameObject,
-    pub sun: GameObject,
-}
-
 fn main() {
-    let app = BenzeneApp::<Option<GameState>>::new(None, Box::new(on_init), Box::new(on_new_frame));
+    BenzeneApp::<()>::builder(())
+        .title("Benzene")
+        .size(1280, 720)
+        .on_init(on_init)
+        .on_new_frame(on_new_frame)
+        .run();
 
     // game_engine.destroy();
 }
 
-pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
+pub fn on_init(engine: &mut GameEngine, _state: &mut ()) {
     log!("On Init");
 
+    engine.load_input_map("assets/input/default.ron");
+
     let mut scene = engine.create_scene();
 
     // Textures
@@ -38,14 +37,20 @@ pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
     // let marble_texture = engine.load_texture_from_image("assets/textures/marble/color.jpg");
 
     // Camera
-    let camera_entity = GameObject::new("Camera");
-    scene.add_game_object(camera_entity.clone());
+    let camera_entity = scene.spawn("Camera");
+    scene.add_transform_3d_component(
+        &camera_entity,
+        Transform3D::new(
+            Vector3::new(0.0, 1.0, 5.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ),
+    );
     scene.add_camera_3d_component(&camera_entity, Camera3D::new_default());
     scene.set_active_camera(&camera_entity);
 
     // Sun
-    let sun = GameObject::new("Sun");
-    scene.add_game_object(sun.clone());
+    let sun = scene.spawn("Sun");
     scene.add_transform_3d_component(
         &sun,
         Transform3D::new(
@@ -58,10 +63,10 @@ pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
         &sun,
         DirectionalLight3D::new(Vector4::new(1.0, 0.95, 0.8, 0.6)),
     );
+    scene.add_tag(&sun, "light");
 
     // Spot light
-    let spot_light_entity = GameObject::new("Spot Light");
-    scene.add_game_object(spot_light_entity.clone());
+    let spot_light_entity = scene.spawn("Spot Light");
     scene.add_transform_3d_component(
         &spot_light_entity,
         Transform3D::new(
@@ -76,8 +81,7 @@ pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
     // );
 
     // Red Light
-    let red_light_entity = GameObject::new("Red Light");
-    scene.add_game_object(red_light_entity.clone());
+    let red_light_entity = scene.spawn("Red Light");
     scene.add_transform_3d_component(
         &red_light_entity,
         Transform3D::new(
@@ -92,10 +96,9 @@ pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
     // );
 
     // Plane
-    let plane_entity = GameObject::new("Plane");
+    let plane_entity = scene.spawn("Plane");
     let plane_structure = engine.get_structure_3d_from_obj("assets/models/plane.obj");
     // let plane_material = engine.get_material_3d_from_texture(grass_texture);
-    scene.add_game_object(plane_entity.clone());
     scene.add_transform_3d_component(
         &plane_entity,
         Transform3D::new(
@@ -108,11 +111,10 @@ pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
     // scene.add_material_3d_component(&plane_entity, plane_material);
 
     // Smooth Vase
-    let smooth_vase_entity = GameObject::new("Smooth Vase");
+    let smooth_vase_entity = scene.spawn("Smooth Vase");
     let smooth_vase: Structure3D =
         engine.get_structure_3d_from_obj("assets/models/vase-smooth.obj");
     // let smooth_vase_material = engine.get_material_3d_from_texture(marble_texture);
-    scene.add_game_object(smooth_vase_entity.clone());
     scene.add_transform_3d_component(
         &smooth_vase_entity,
         Transform3D::new(
@@ -125,25 +127,16 @@ pub fn on_init(engine: &mut GameEngine, state: &mut Option<GameState>) {
     // scene.add_material_3d_component(&smooth_vase_entity, smooth_vase_material);
 
     engine.set_active_scene(scene);
-
-    *state = Some(GameState {
-        camera: camera_entity,
-        spot_light: spot_light_entity,
-        plane: plane_entity,
-        sun: sun,
-    });
 }
 
-pub fn on_new_frame(engine: &mut GameEngine, state: &mut Option<GameState>) {
-    // let game_state = state.as_ref().unwrap();
-
+pub fn on_new_frame(engine: &mut GameEngine, _state: &mut ()) {
     // let dt = engine.frame_time;
 
     // let scene = engine.get_active_scene();
-    // let sun_transform_3d = scene.get_transform_3d_component(&game_state.sun);
+    // let sun = scene.find_by_name("Sun").unwrap();
+    // let sun_transform_3d = scene.get_transform_3d_component(&sun);
     // rotate_transform_3d(
     //     sun_transform_3d,
     //     Vector3::new(0.0, 0.0, 1.0 * dt.as_secs_f32()),
     // );
-    // scene.mark_directional_light_3d_dirty();
 }
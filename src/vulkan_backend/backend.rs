@@ -1,3 +1,4 @@
+use ash::vk;
 use winit::window::Window;
 
 use crate::{
@@ -5,33 +6,37 @@ use crate::{
     log,
     vulkan_backend::{
         backend_event::VBackendEvent,
-        device::{VDevice, VPhysicalDevice, config::VPhysicalDeviceConfig},
+        config::VBackendConfig,
+        device::{VDevice, VPhysicalDevice},
         frame::{VFrameRenderResult, VFrameRenderer, context::VFrameRenderContext},
-        instance::{VInstance, VInstanceConfig},
+        instance::VInstance,
         memory::VMemoryManager,
+        offscreen::VOffscreen,
+        render_target::VRenderTarget,
         surface::VSurface,
-        swapchain::VSwapchain,
+        swapchain::{VSwapchain, config::VSwapchainConfig},
     },
 };
 
 pub struct VBackend {
     pub v_instance: VInstance,
-    pub v_surface: VSurface,
+    pub v_surface: Option<VSurface>,
     pub v_physical_device: VPhysicalDevice,
     pub v_device: VDevice,
     pub v_memory_manager: VMemoryManager,
-    pub v_swapchain: VSwapchain,
+    pub v_render_target: VRenderTarget,
     pub v_frame_renderer: VFrameRenderer,
+    swapchain_config: VSwapchainConfig,
 }
 
 impl VBackend {
-    pub fn new(window: &Window) -> Self {
-        let v_instance = VInstance::new(&window, VInstanceConfig::default());
+    pub fn new(window: &Window, config: VBackendConfig) -> Self {
+        let v_instance = VInstance::new(Some(window), config.instance_config);
         let v_surface = VSurface::new(&window, &v_instance);
         let mut v_physical_devices = VPhysicalDevice::get_compatible_devices(
             &v_instance,
-            &v_surface,
-            VPhysicalDeviceConfig::default(),
+            Some(&v_surface),
+            config.physical_device_config,
         );
         let v_physical_device = v_physical_devices.remove(0);
 
@@ -40,7 +45,7 @@ impl VBackend {
             v_physical_device.properties.device_name_as_c_str()
         ));
 
-        let v_device = VDevice::new(&v_instance, &v_surface, &v_physical_device);
+        let v_device = VDevice::new(&v_instance, Some(&v_surface), &v_physical_device);
         let v_memory_manager: VMemoryManager = VMemoryManager::new(&v_device);
         let v_swapchain = VSwapchain::new(
             &window,
@@ -49,36 +54,143 @@ impl VBackend {
             &v_physical_device,
             &v_device,
             &v_memory_manager,
+            &config.swapchain_config,
         );
         let v_frame_renderer = VFrameRenderer::new(&v_device, MAX_FRAMES_IN_FLIGHT);
 
         Self {
             v_instance,
-            v_surface,
+            v_surface: Some(v_surface),
+            v_physical_device,
+            v_device,
+            v_memory_manager,
+            v_render_target: VRenderTarget::Swapchain(v_swapchain),
+            v_frame_renderer,
+            swapchain_config: config.swapchain_config,
+        }
+    }
+
+    pub fn new_headless(image_extent: vk::Extent2D, config: VBackendConfig) -> Self {
+        let v_instance = VInstance::new(None, config.instance_config);
+        let mut v_physical_devices = VPhysicalDevice::get_compatible_devices(
+            &v_instance,
+            None,
+            config.physical_device_config,
+        );
+        let v_physical_device = v_physical_devices.remove(0);
+
+        log!(format!(
+            "Selected headless device: {:?}",
+            v_physical_device.properties.device_name_as_c_str()
+        ));
+
+        let v_device = VDevice::new(&v_instance, None, &v_physical_device);
+        let v_memory_manager: VMemoryManager = VMemoryManager::new(&v_device);
+        let v_offscreen = VOffscreen::new(
+            &v_instance,
+            &v_physical_device,
+            &v_device,
+            &v_memory_manager,
+            image_extent,
+            MAX_FRAMES_IN_FLIGHT,
+        );
+        let v_frame_renderer = VFrameRenderer::new(&v_device, MAX_FRAMES_IN_FLIGHT);
+
+        Self {
+            v_instance,
+            v_surface: None,
             v_physical_device,
             v_device,
             v_memory_manager,
-            v_swapchain,
+            v_render_target: VRenderTarget::Offscreen(v_offscreen),
             v_frame_renderer,
+            swapchain_config: config.swapchain_config,
         }
     }
 
+    pub fn is_headless(&self) -> bool {
+        self.v_render_target.is_offscreen()
+    }
+
     pub fn recreate_swapchain(&mut self, window: &Window) {
+        let v_surface = self
+            .v_surface
+            .as_ref()
+            .expect("cannot recreate swapchain on a headless backend");
+
         self.v_device.wait_till_idle();
-        self.v_swapchain
+        self.v_render_target
             .destroy(&self.v_device, &self.v_memory_manager);
-        self.v_swapchain = VSwapchain::new(
+        self.v_render_target = VRenderTarget::Swapchain(VSwapchain::new(
             window,
             &self.v_instance,
-            &self.v_surface,
+            v_surface,
             &self.v_physical_device,
             &self.v_device,
             &self.v_memory_manager,
-        );
+            &self.swapchain_config,
+        ));
 
+        let extent = self.v_render_target.image_extent();
         log!(format!(
             "Recreated Swapchain, width: {}, height: {}",
-            self.v_swapchain.image_extent.width, self.v_swapchain.image_extent.height
+            extent.width, extent.height
+        ));
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.v_render_target.is_suspended()
+    }
+
+    // Destroys the swapchain and surface. Everything else, including the
+    // device and all resources created on it, stays alive until `resume`.
+    pub fn suspend(&mut self) {
+        if self.is_headless() || self.is_suspended() {
+            return;
+        }
+
+        self.v_device.wait_till_idle();
+        let suspended = self.v_render_target.to_suspended();
+        self.v_render_target
+            .destroy(&self.v_device, &self.v_memory_manager);
+        self.v_render_target = suspended;
+        if let Some(v_surface) = self.v_surface.take() {
+            v_surface.destroy();
+        }
+
+        log!("Suspended, surface and swapchain destroyed");
+    }
+
+    pub fn resume(&mut self, window: &Window) {
+        if !self.is_suspended() {
+            return;
+        }
+
+        self.v_surface = Some(VSurface::new(window, &self.v_instance));
+        self.recreate_swapchain(window);
+    }
+
+    pub fn recreate_offscreen(&mut self, image_extent: vk::Extent2D) {
+        assert!(
+            self.is_headless(),
+            "cannot recreate offscreen images on a windowed backend"
+        );
+
+        self.v_device.wait_till_idle();
+        self.v_render_target
+            .destroy(&self.v_device, &self.v_memory_manager);
+        self.v_render_target = VRenderTarget::Offscreen(VOffscreen::new(
+            &self.v_instance,
+            &self.v_physical_device,
+            &self.v_device,
+            &self.v_memory_manager,
+            image_extent,
+            MAX_FRAMES_IN_FLIGHT,
+        ));
+
+        log!(format!(
+            "Recreated offscreen images, width: {}, height: {}",
+            image_extent.width, image_extent.height
         ));
     }
 
@@ -92,7 +204,7 @@ impl VBackend {
                 self.recreate_swapchain(window);
                 Some(VBackendEvent::UpdateFramebuffers(
                     &self.v_device,
-                    &self.v_swapchain,
+                    &self.v_render_target,
                 ))
             }
             _ => None,
@@ -101,16 +213,29 @@ impl VBackend {
 
     pub fn render(&self, render: impl Fn(VFrameRenderContext) -> ()) -> VFrameRenderResult {
         self.v_frame_renderer
-            .render(&self.v_device, &self.v_swapchain, render)
+            .render(&self.v_device, &self.v_render_target, render)
+    }
+
+    // RGBA8 pixels of the last frame rendered offscreen
+    pub fn read_pixels(&mut self) -> Option<Vec<u8>> {
+        match &mut self.v_render_target {
+            VRenderTarget::Offscreen(v_offscreen) => {
+                self.v_device.wait_till_idle();
+                v_offscreen.read_pixels(&self.v_device, &self.v_memory_manager)
+            }
+            VRenderTarget::Swapchain(_) | VRenderTarget::Suspended { .. } => None,
+        }
     }
 
     pub fn destroy(&self) {
         self.v_frame_renderer.destroy(&self.v_device);
-        self.v_swapchain
+        self.v_render_target
             .destroy(&self.v_device, &self.v_memory_manager);
         self.v_memory_manager.destroy(&self.v_device);
         self.v_device.destroy();
-        self.v_surface.destroy();
+        if let Some(v_surface) = &self.v_surface {
+            v_surface.destroy();
+        }
         self.v_instance.destroy();
     }
 }
@@ -1,6 +1,6 @@
-use crate::vulkan_backend::{device::VDevice, swapchain::VSwapchain};
+use crate::vulkan_backend::{device::VDevice, render_target::VRenderTarget};
 
 pub enum VBackendEvent<'a> {
-    UpdateFramebuffers(&'a VDevice, &'a VSwapchain),
+    UpdateFramebuffers(&'a VDevice, &'a VRenderTarget),
     None,
 }
@@ -0,0 +1,30 @@
+use crate::vulkan_backend::{
+    device::config::VPhysicalDeviceConfig, instance::VInstanceConfig,
+    swapchain::config::VSwapchainConfig,
+};
+
+pub struct VBackendConfig {
+    pub instance_config: VInstanceConfig,
+    pub physical_device_config: VPhysicalDeviceConfig,
+    pub swapchain_config: VSwapchainConfig,
+}
+
+impl VBackendConfig {
+    pub fn headless() -> Self {
+        Self {
+            instance_config: VInstanceConfig::default(),
+            physical_device_config: VPhysicalDeviceConfig::headless(),
+            swapchain_config: VSwapchainConfig::default(),
+        }
+    }
+}
+
+impl Default for VBackendConfig {
+    fn default() -> Self {
+        Self {
+            instance_config: VInstanceConfig::default(),
+            physical_device_config: VPhysicalDeviceConfig::default(),
+            swapchain_config: VSwapchainConfig::default(),
+        }
+    }
+}
@@ -19,4 +19,6 @@ pub struct VDescriptorPoolTypeConfig {
 pub struct VDescriptorPoolConfig {
     pub types: Vec<VDescriptorPoolTypeConfig>,
     pub max_sets: u32,
+    // FREE_DESCRIPTOR_SET allows freeing sets one by one
+    pub flags: vk::DescriptorPoolCreateFlags,
 }
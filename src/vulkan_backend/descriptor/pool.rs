@@ -21,7 +21,8 @@ impl VDescriptorPool {
 
         let pool_info = vk::DescriptorPoolCreateInfo::default()
             .pool_sizes(&pool_sizes)
-            .max_sets(config.max_sets);
+            .max_sets(config.max_sets)
+            .flags(config.flags);
 
         let pool = unsafe {
             v_device
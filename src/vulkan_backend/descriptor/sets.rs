@@ -86,6 +86,16 @@ impl VDescriptorSet {
         );
     }
 
+    // The pool must be created with FREE_DESCRIPTOR_SET
+    pub fn free(&self, v_device: &VDevice, pool: vk::DescriptorPool) {
+        unsafe {
+            v_device
+                .device
+                .free_descriptor_sets(pool, std::slice::from_ref(&self.set))
+                .expect("failed to free descriptor set");
+        }
+    }
+
     // pub fn queue_image_all_sets(
     //     &self,
     //     batch: &mut VDescriptorWriteBatch,
@@ -3,6 +3,8 @@ use ash::vk;
 pub struct VPhysicalDeviceConfig {
     pub required_extensions: Vec<String>,
     pub required_queue_flags: Vec<vk::QueueFlags>,
+    // Devices whose name contains this (case insensitive) are picked first
+    pub preferred_device_name: Option<String>,
 }
 
 impl VPhysicalDeviceConfig {
@@ -10,6 +12,16 @@ impl VPhysicalDeviceConfig {
         Self {
             required_extensions: vec!["VK_KHR_swapchain".into()],
             required_queue_flags: vec![vk::QueueFlags::GRAPHICS],
+            preferred_device_name: None,
+        }
+    }
+
+    // No surface to present to, so the swapchain extension is not required
+    pub fn headless() -> Self {
+        Self {
+            required_extensions: vec![],
+            required_queue_flags: vec![vk::QueueFlags::GRAPHICS],
+            preferred_device_name: None,
         }
     }
 }
@@ -9,11 +9,11 @@ pub struct VDevice {
     pub device: Device,
     pub graphics_queue_family_index: u32,
     pub transfer_queue_family_index: u32,
-    pub present_queue_family_index: u32,
+    pub present_queue_family_index: Option<u32>,
     pub unique_queue_family_indices: Vec<u32>,
     pub graphics_queue: vk::Queue,
     pub transfer_queue: vk::Queue,
-    pub present_queue: vk::Queue,
+    pub present_queue: Option<vk::Queue>,
     pub is_graphics_and_transfer_queue_same: bool,
     pub is_graphics_and_present_queue_same: bool,
 
@@ -25,7 +25,7 @@ pub struct VDevice {
 impl VDevice {
     pub fn new(
         v_instance: &VInstance,
-        v_surface: &VSurface,
+        v_surface: Option<&VSurface>,
         v_physical_device: &VPhysicalDevice,
     ) -> Self {
         let graphics_queue_family_index = v_physical_device
@@ -36,14 +36,19 @@ impl VDevice {
             .get_transfer_queue_family_index()
             .unwrap_or(graphics_queue_family_index);
 
-        let present_queue_family_index = v_physical_device
-            .get_present_queue_family_index(v_surface)
-            .expect("failed to find present queue");
+        // Headless devices never present, so they don't get a present queue
+        let present_queue_family_index = v_surface.map(|v_surface| {
+            v_physical_device
+                .get_present_queue_family_index(v_surface)
+                .expect("failed to find present queue")
+        });
 
         let is_graphics_and_transfer_queue_same =
             graphics_queue_family_index == transfer_queue_family_index;
-        let is_graphics_and_present_queue_same =
-            graphics_queue_family_index == present_queue_family_index;
+        let is_graphics_and_present_queue_same = match present_queue_family_index {
+            Some(index) => graphics_queue_family_index == index,
+            None => true,
+        };
 
         let mut unique_queue_family_indices = vec![graphics_queue_family_index];
         let mut buffer_queue_family_indices = vec![graphics_queue_family_index];
@@ -51,8 +56,11 @@ impl VDevice {
             unique_queue_family_indices.push(transfer_queue_family_index);
             buffer_queue_family_indices.push(transfer_queue_family_index);
         }
-        if !is_graphics_and_present_queue_same {
-            unique_queue_family_indices.push(present_queue_family_index);
+        if let (false, Some(index)) = (
+            is_graphics_and_present_queue_same,
+            present_queue_family_index,
+        ) {
+            unique_queue_family_indices.push(index);
         }
         let queue_infos: Vec<vk::DeviceQueueCreateInfo> = unique_queue_family_indices
             .iter()
@@ -85,7 +93,8 @@ impl VDevice {
 
         let graphics_queue = unsafe { device.get_device_queue(graphics_queue_family_index, 0) };
         let transfer_queue = unsafe { device.get_device_queue(transfer_queue_family_index, 0) };
-        let present_queue = unsafe { device.get_device_queue(present_queue_family_index, 0) };
+        let present_queue = present_queue_family_index
+            .map(|index| unsafe { device.get_device_queue(index, 0) });
 
         let buffer_sharing_mode = if buffer_queue_family_indices.len() > 1 {
             vk::SharingMode::CONCURRENT
@@ -1,5 +1,6 @@
 use ash::vk;
 
+use crate::log;
 use crate::vulkan_backend::{
     device::config::VPhysicalDeviceConfig, instance::VInstance, surface::VSurface,
 };
@@ -110,10 +111,21 @@ impl VPhysicalDevice {
         panic!("failed to find format with support")
     }
 
-    pub fn select_present_mode(&self) -> vk::PresentModeKHR {
-        for each in self.present_modes.iter() {
-            if *each == vk::PresentModeKHR::MAILBOX {
-                return vk::PresentModeKHR::MAILBOX;
+    pub fn get_device_name(&self) -> String {
+        self.properties
+            .device_name_as_c_str()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    // FIFO is vsync and always available. Without vsync prefer MAILBOX, then IMMEDIATE.
+    pub fn select_present_mode(&self, vsync: bool) -> vk::PresentModeKHR {
+        if vsync {
+            return vk::PresentModeKHR::FIFO;
+        }
+        for preferred in [vk::PresentModeKHR::MAILBOX, vk::PresentModeKHR::IMMEDIATE] {
+            if self.present_modes.contains(&preferred) {
+                return preferred;
             }
         }
         return vk::PresentModeKHR::FIFO;
@@ -138,7 +150,7 @@ impl VPhysicalDevice {
 
     pub fn get_compatible_devices(
         v_instance: &VInstance,
-        v_surface: &VSurface,
+        v_surface: Option<&VSurface>,
         config: VPhysicalDeviceConfig,
     ) -> Vec<VPhysicalDevice> {
         let physical_devices = unsafe {
@@ -178,24 +190,34 @@ impl VPhysicalDevice {
                 }
             }
 
-            let surface_formats = unsafe {
-                v_surface
-                    .surface_instance
-                    .get_physical_device_surface_formats(each_device, v_surface.surface)
-                    .expect("failed to get device surface formats")
-            };
-            if surface_formats.is_empty() {
-                break;
-            }
-            let present_modes = unsafe {
-                v_surface
-                    .surface_instance
-                    .get_physical_device_surface_present_modes(each_device, v_surface.surface)
-                    .expect("failed to get device present modesl")
+            let (surface_formats, present_modes) = match v_surface {
+                Some(v_surface) => {
+                    let surface_formats = unsafe {
+                        v_surface
+                            .surface_instance
+                            .get_physical_device_surface_formats(each_device, v_surface.surface)
+                            .expect("failed to get device surface formats")
+                    };
+                    if surface_formats.is_empty() {
+                        break;
+                    }
+                    let present_modes = unsafe {
+                        v_surface
+                            .surface_instance
+                            .get_physical_device_surface_present_modes(
+                                each_device,
+                                v_surface.surface,
+                            )
+                            .expect("failed to get device present modesl")
+                    };
+                    if present_modes.is_empty() {
+                        break;
+                    }
+                    (surface_formats, present_modes)
+                }
+                // Headless: nothing is presented, so no surface support is needed
+                None => (vec![], vec![]),
             };
-            if present_modes.is_empty() {
-                break;
-            }
             let queue_families = unsafe {
                 v_instance
                     .instance
@@ -239,6 +261,22 @@ impl VPhysicalDevice {
         }
 
         compatible_physical_devices.sort_by(|a, b| b.score.cmp(&a.score));
+        if let Some(preferred) = &config.preferred_device_name {
+            let preferred = preferred.to_lowercase();
+            match compatible_physical_devices
+                .iter()
+                .position(|each| each.get_device_name().to_lowercase().contains(&preferred))
+            {
+                Some(index) => {
+                    let device = compatible_physical_devices.remove(index);
+                    compatible_physical_devices.insert(0, device);
+                }
+                None => log!(format!(
+                    "Preferred device '{}' not found, using the default selection",
+                    preferred
+                )),
+            }
+        }
         compatible_physical_devices
     }
 }
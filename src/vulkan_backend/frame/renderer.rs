@@ -5,6 +5,8 @@ use ash::vk;
 use crate::vulkan_backend::{
     device::VDevice,
     frame::{VFrameRenderResult, context::VFrameRenderContext},
+    offscreen::VOffscreen,
+    render_target::VRenderTarget,
     swapchain::VSwapchain,
 };
 
@@ -90,6 +92,23 @@ impl VFrameRenderer {
     }
 
     pub fn render(
+        &self,
+        v_device: &VDevice,
+        v_render_target: &VRenderTarget,
+        render: impl Fn(VFrameRenderContext) -> (),
+    ) -> VFrameRenderResult {
+        match v_render_target {
+            VRenderTarget::Swapchain(v_swapchain) => {
+                self.render_to_swapchain(v_device, v_swapchain, render)
+            }
+            VRenderTarget::Offscreen(v_offscreen) => {
+                self.render_to_offscreen(v_device, v_offscreen, render)
+            }
+            VRenderTarget::Suspended { .. } => VFrameRenderResult::Skipped,
+        }
+    }
+
+    pub fn render_to_swapchain(
         &self,
         v_device: &VDevice,
         v_swapchain: &VSwapchain,
@@ -117,6 +136,67 @@ impl VFrameRenderer {
         }
     }
 
+    // No image acquisition or presentation: each frame in flight owns one
+    // offscreen image, which is copied to its readback buffer after rendering
+    pub fn render_to_offscreen(
+        &self,
+        v_device: &VDevice,
+        v_offscreen: &VOffscreen,
+        render: impl Fn(VFrameRenderContext) -> (),
+    ) -> VFrameRenderResult {
+        let frame_index = self.frame_index.get();
+        let image_index = frame_index % v_offscreen.image_ids.len();
+        let cmd = self.command_buffers[frame_index];
+
+        unsafe {
+            v_device
+                .device
+                .wait_for_fences(
+                    &self.buffer_free_fences[frame_index..frame_index + 1],
+                    true,
+                    u64::MAX,
+                )
+                .expect("failed to wait for fence");
+            v_device
+                .device
+                .reset_fences(&self.buffer_free_fences[frame_index..frame_index + 1])
+                .expect("failed to reset fence");
+            v_device
+                .device
+                .begin_command_buffer(cmd, &vk::CommandBufferBeginInfo::default())
+                .expect("failed to start command buffer");
+        }
+
+        render(VFrameRenderContext {
+            index: frame_index,
+            cmd,
+            image_id: v_offscreen.image_ids[image_index],
+        });
+
+        v_offscreen.record_readback(v_device, cmd, image_index);
+
+        unsafe {
+            v_device
+                .device
+                .end_command_buffer(cmd)
+                .expect("failed to end command buffer");
+
+            v_device
+                .device
+                .queue_submit(
+                    v_device.graphics_queue,
+                    &[vk::SubmitInfo::default().command_buffers(&[cmd])],
+                    self.buffer_free_fences[frame_index],
+                )
+                .expect("failed to submit command buffer");
+        }
+
+        v_offscreen.last_rendered_image.set(Some(image_index));
+        self.frame_index
+            .replace((frame_index + 1) % self.total_frames);
+        VFrameRenderResult::Ok
+    }
+
     pub fn start_frame(
         &self,
         v_device: &VDevice,
@@ -200,7 +280,9 @@ impl VFrameRenderer {
                 .expect("failed to submit command buffer");
 
             let queue_present_result = v_swapchain.swapchain_device.queue_present(
-                v_device.present_queue,
+                v_device
+                    .present_queue
+                    .expect("swapchain requires a device with a present queue"),
                 &vk::PresentInfoKHR::default()
                     .swapchains(&[v_swapchain.swapchain])
                     .image_indices(&[image_index as u32])
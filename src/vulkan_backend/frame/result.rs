@@ -1,4 +1,6 @@
 pub enum VFrameRenderResult {
     Ok,
     RecreateSwapchain,
+    // Nothing to render into, e.g. while suspended
+    Skipped,
 }
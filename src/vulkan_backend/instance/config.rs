@@ -2,7 +2,9 @@ pub struct VInstanceConfig {
     pub application_name: String,
     pub extensions: Vec<String>,
     pub layers: Vec<String>,
+    // Enables VK_LAYER_KHRONOS_validation when it is installed
     pub enable_validation: bool,
+    // Routes validation messages through the debug utils messenger
     pub enable_debug: bool,
 }
 
@@ -4,6 +4,10 @@ use std::ffi::CString;
 use winit::raw_window_handle::HasDisplayHandle;
 use winit::window::Window;
 
+use crate::log;
+
+const VALIDATION_LAYER: &str = "VK_LAYER_KHRONOS_validation";
+
 pub struct VInstance {
     pub entry: Entry,
     pub instance: Instance,
@@ -12,7 +16,7 @@ pub struct VInstance {
 }
 
 impl VInstance {
-    pub fn new(window: &Window, config: super::VInstanceConfig) -> Self {
+    pub fn new(window: Option<&Window>, config: super::VInstanceConfig) -> Self {
         let entry = Entry::linked();
 
         let app_name = CString::new(config.application_name).unwrap();
@@ -23,10 +27,15 @@ impl VInstance {
             .engine_name(&app_name)
             .engine_version(0);
 
-        let mut extensions =
-            ash_window::enumerate_required_extensions(window.display_handle().unwrap().as_raw())
-                .expect("failed to fetch required window extensions")
-                .to_vec();
+        // Headless instances don't need any surface extensions
+        let mut extensions = match window {
+            Some(window) => {
+                ash_window::enumerate_required_extensions(window.display_handle().unwrap().as_raw())
+                    .expect("failed to fetch required window extensions")
+                    .to_vec()
+            }
+            None => vec![],
+        };
 
         extensions.extend(config.extensions.iter().map(|e| e.as_ptr() as *const i8));
         let debug_utils_extension = c"VK_EXT_debug_utils";
@@ -35,8 +44,15 @@ impl VInstance {
         }
 
         let mut layers = config.layers;
-        if config.enable_debug {
-            layers.push("VK_LAYER_KHRONOS_validation".into());
+        if config.enable_validation {
+            if VInstance::is_layer_available(&entry, VALIDATION_LAYER) {
+                layers.push(VALIDATION_LAYER.into());
+            } else {
+                log!(format!(
+                    "{} requested but not installed, continuing without validation",
+                    VALIDATION_LAYER
+                ));
+            }
         }
         let p_layers: Vec<*const i8> = layers
             .iter()
@@ -73,6 +89,19 @@ impl VInstance {
         }
     }
 
+    pub fn is_layer_available(entry: &Entry, layer_name: &str) -> bool {
+        let layer_properties = unsafe {
+            entry
+                .enumerate_instance_layer_properties()
+                .expect("failed to enumerate instance layers")
+        };
+        layer_properties.iter().any(|each| {
+            each.layer_name_as_c_str()
+                .map(|name| name.to_str() == Ok(layer_name))
+                .unwrap_or(false)
+        })
+    }
+
     pub fn setup_debug_messenger(
         entry: &Entry,
         instance: &Instance,
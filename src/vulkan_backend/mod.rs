@@ -1,12 +1,15 @@
 pub mod backend;
 pub mod backend_event;
+pub mod config;
 pub mod descriptor;
 pub mod device;
 pub mod frame;
 pub mod instance;
 pub mod memory;
+pub mod offscreen;
 pub mod pipeline;
 pub mod push_constant;
+pub mod render_target;
 pub mod surface;
 pub mod swapchain;
 pub mod vertex_input;
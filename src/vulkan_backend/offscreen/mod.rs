@@ -0,0 +1,213 @@
+use std::cell::Cell;
+
+use ash::vk;
+
+use crate::shared::types::Id;
+use crate::vulkan_backend::device::{VDevice, VPhysicalDevice};
+use crate::vulkan_backend::instance::VInstance;
+use crate::vulkan_backend::memory::image::config::VImageConfig;
+use crate::vulkan_backend::memory::image::{VImage, image_view::VImageView};
+use crate::vulkan_backend::memory::{VBuffer, VBufferConfig, VMemoryManager, VMemoryState};
+
+// Color images rendered without a surface. Every image has a host visible
+// buffer the frame is copied into, so it can be read back on the CPU.
+pub struct VOffscreen {
+    pub image_extent: vk::Extent2D,
+    pub color_format: vk::Format,
+    pub image_ids: Vec<Id>,
+    pub v_images: Vec<VImage>,
+    pub v_image_views: Vec<VImageView>,
+    pub readback_buffers: Vec<VBuffer>,
+    pub depth_v_image: VImage,
+    pub depth_v_image_view: VImageView,
+    pub depth_format: vk::Format,
+    pub last_rendered_image: Cell<Option<usize>>,
+}
+
+impl VOffscreen {
+    pub const COLOR_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+    pub fn new(
+        v_instance: &VInstance,
+        v_physical_device: &VPhysicalDevice,
+        v_device: &VDevice,
+        v_memory_manager: &VMemoryManager,
+        image_extent: vk::Extent2D,
+        image_count: usize,
+    ) -> Self {
+        let extent_3d = vk::Extent3D {
+            width: image_extent.width,
+            height: image_extent.height,
+            depth: 1,
+        };
+        let image_size = image_extent.width as u64 * image_extent.height as u64 * 4;
+
+        let image_ids: Vec<Id> = (0..image_count).map(|_| rand::random()).collect();
+
+        let v_images: Vec<VImage> = (0..image_count)
+            .map(|_| {
+                VImage::new(
+                    v_device,
+                    v_physical_device,
+                    v_memory_manager,
+                    VImageConfig::image_2d(
+                        extent_3d,
+                        image_size,
+                        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+                        vk::SharingMode::EXCLUSIVE,
+                        None,
+                        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                        Self::COLOR_FORMAT,
+                    ),
+                )
+            })
+            .collect();
+
+        let v_image_views: Vec<VImageView> = v_images
+            .iter()
+            .map(|v_image| {
+                VImageView::new_2d(
+                    v_device,
+                    v_image,
+                    vk::ImageAspectFlags::COLOR,
+                    Self::COLOR_FORMAT,
+                )
+            })
+            .collect();
+
+        let readback_buffers: Vec<VBuffer> = (0..image_count)
+            .map(|_| {
+                VBuffer::new(
+                    v_device,
+                    v_physical_device,
+                    v_memory_manager,
+                    VBufferConfig {
+                        size: image_size,
+                        usage: vk::BufferUsageFlags::TRANSFER_DST,
+                        sharing_mode: vk::SharingMode::EXCLUSIVE,
+                        queue_families: None,
+                        memory_property: vk::MemoryPropertyFlags::HOST_VISIBLE
+                            | vk::MemoryPropertyFlags::HOST_COHERENT,
+                    },
+                )
+            })
+            .collect();
+
+        let depth_format = v_physical_device.get_format_for_depth_stencil(v_instance);
+
+        let depth_v_image = VImage::new(
+            v_device,
+            v_physical_device,
+            v_memory_manager,
+            VImageConfig::image_2d(
+                extent_3d,
+                image_size,
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                vk::SharingMode::EXCLUSIVE,
+                None,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                depth_format,
+            ),
+        );
+        let depth_v_image_view = VImageView::new_2d(
+            v_device,
+            &depth_v_image,
+            vk::ImageAspectFlags::DEPTH,
+            depth_format,
+        );
+
+        Self {
+            image_extent,
+            color_format: Self::COLOR_FORMAT,
+            image_ids,
+            v_images,
+            v_image_views,
+            readback_buffers,
+            depth_v_image,
+            depth_v_image_view,
+            depth_format,
+            last_rendered_image: Cell::new(None),
+        }
+    }
+
+    // Expects the image to be in TRANSFER_SRC_OPTIMAL, which is the final layout
+    // of the render pass when rendering offscreen. The pass' dependency on
+    // SUBPASS_EXTERNAL orders the copy after its writes and that transition.
+    pub fn record_readback(&self, v_device: &VDevice, cmd: vk::CommandBuffer, image_index: usize) {
+        let subresource_layer = vk::ImageSubresourceLayers::default()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(0)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let region = vk::BufferImageCopy::default()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+            .image_extent(self.v_images[image_index].config.extent)
+            .image_subresource(subresource_layer);
+
+        let after_copy = vk::MemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ);
+
+        unsafe {
+            v_device.device.cmd_copy_image_to_buffer(
+                cmd,
+                self.v_images[image_index].image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                self.readback_buffers[image_index].buffer,
+                &[region],
+            );
+            v_device.device.cmd_pipeline_barrier(
+                cmd,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[after_copy],
+                &[],
+                &[],
+            );
+        }
+    }
+
+    // Tightly packed RGBA8 pixels of the last rendered frame. The caller must
+    // make sure the frame has finished executing on the device.
+    pub fn read_pixels(
+        &mut self,
+        v_device: &VDevice,
+        v_memory_manager: &VMemoryManager,
+    ) -> Option<Vec<u8>> {
+        let image_index = self.last_rendered_image.get()?;
+        let v_buffer = &mut self.readback_buffers[image_index];
+        let size = v_buffer.config.size;
+
+        let mapped = v_buffer.v_memory.map(v_device, v_memory_manager, 0, size);
+        let pixels = match mapped {
+            VMemoryState::MAPPED(_, _, src) => {
+                let mut pixels = vec![0u8; size as usize];
+                unsafe { std::ptr::copy_nonoverlapping(src, pixels.as_mut_ptr(), size as usize) };
+                pixels
+            }
+            VMemoryState::UNMAPPED => return None,
+        };
+        v_buffer.v_memory.unmap(v_device, v_memory_manager);
+
+        Some(pixels)
+    }
+
+    pub fn destroy(&self, v_device: &VDevice, v_memory_manager: &VMemoryManager) {
+        self.depth_v_image_view.destroy(v_device);
+        self.depth_v_image.destroy(v_device, v_memory_manager);
+        for v_image_view in self.v_image_views.iter() {
+            v_image_view.destroy(v_device);
+        }
+        for v_image in self.v_images.iter() {
+            v_image.destroy(v_device, v_memory_manager);
+        }
+        for v_buffer in self.readback_buffers.iter() {
+            v_buffer.destroy(v_device, v_memory_manager);
+        }
+    }
+}
@@ -5,4 +5,5 @@ pub struct VPipelineInfoConfig {
     pub attribute_descriptions: Vec<vk::VertexInputAttributeDescription>,
     pub vertex_shader_file: Option<String>,
     pub fragment_shader_file: Option<String>,
+    pub depth_compare_op: vk::CompareOp,
 }
@@ -138,7 +138,7 @@ impl VPipelineInfo {
         vk::PipelineDepthStencilStateCreateInfo::default()
             .depth_test_enable(true)
             .depth_write_enable(true)
-            .depth_compare_op(vk::CompareOp::LESS)
+            .depth_compare_op(self.config.depth_compare_op)
             .depth_bounds_test_enable(false)
             .stencil_test_enable(false)
     }
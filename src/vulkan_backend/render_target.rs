@@ -0,0 +1,109 @@
+use ash::vk;
+
+use crate::{
+    shared::types::Id,
+    vulkan_backend::{
+        device::VDevice,
+        memory::{VMemoryManager, image::image_view::VImageView},
+        offscreen::VOffscreen,
+        swapchain::VSwapchain,
+    },
+};
+
+// Where frames end up: presented to a window surface or kept offscreen.
+// While the app is suspended there is no surface, only the last known extent
+// and formats are kept so the swapchain can be rebuilt compatibly on resume.
+pub enum VRenderTarget {
+    Swapchain(VSwapchain),
+    Offscreen(VOffscreen),
+    Suspended {
+        image_extent: vk::Extent2D,
+        color_format: vk::Format,
+        depth_format: vk::Format,
+    },
+}
+
+impl VRenderTarget {
+    pub fn is_offscreen(&self) -> bool {
+        matches!(self, VRenderTarget::Offscreen(_))
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        matches!(self, VRenderTarget::Suspended { .. })
+    }
+
+    // Keeps the extent and formats, the images themselves are destroyed by the caller
+    pub fn to_suspended(&self) -> VRenderTarget {
+        VRenderTarget::Suspended {
+            image_extent: self.image_extent(),
+            color_format: self.color_format(),
+            depth_format: self.depth_format(),
+        }
+    }
+
+    pub fn image_extent(&self) -> vk::Extent2D {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => v_swapchain.image_extent,
+            VRenderTarget::Offscreen(v_offscreen) => v_offscreen.image_extent,
+            VRenderTarget::Suspended { image_extent, .. } => *image_extent,
+        }
+    }
+
+    pub fn image_ids(&self) -> &[Id] {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => &v_swapchain.image_ids,
+            VRenderTarget::Offscreen(v_offscreen) => &v_offscreen.image_ids,
+            VRenderTarget::Suspended { .. } => &[],
+        }
+    }
+
+    pub fn v_image_views(&self) -> &[VImageView] {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => &v_swapchain.v_image_views,
+            VRenderTarget::Offscreen(v_offscreen) => &v_offscreen.v_image_views,
+            VRenderTarget::Suspended { .. } => &[],
+        }
+    }
+
+    pub fn depth_v_image_view(&self) -> Option<&VImageView> {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => Some(&v_swapchain.depth_v_image_view),
+            VRenderTarget::Offscreen(v_offscreen) => Some(&v_offscreen.depth_v_image_view),
+            VRenderTarget::Suspended { .. } => None,
+        }
+    }
+
+    pub fn color_format(&self) -> vk::Format {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => v_swapchain.v_images[0].config.format,
+            VRenderTarget::Offscreen(v_offscreen) => v_offscreen.color_format,
+            VRenderTarget::Suspended { color_format, .. } => *color_format,
+        }
+    }
+
+    pub fn depth_format(&self) -> vk::Format {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => v_swapchain.depth_format,
+            VRenderTarget::Offscreen(v_offscreen) => v_offscreen.depth_format,
+            VRenderTarget::Suspended { depth_format, .. } => *depth_format,
+        }
+    }
+
+    // Layout color images are left in once the geometry pass is done
+    pub fn final_color_layout(&self) -> vk::ImageLayout {
+        match self {
+            VRenderTarget::Swapchain(_) | VRenderTarget::Suspended { .. } => {
+                vk::ImageLayout::PRESENT_SRC_KHR
+            }
+            VRenderTarget::Offscreen(_) => vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        }
+    }
+
+    pub fn destroy(&self, v_device: &VDevice, v_memory_manager: &VMemoryManager) {
+        match self {
+            VRenderTarget::Swapchain(v_swapchain) => v_swapchain.destroy(v_device, v_memory_manager),
+            VRenderTarget::Offscreen(v_offscreen) => v_offscreen.destroy(v_device, v_memory_manager),
+            VRenderTarget::Suspended { .. } => (),
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub struct VSwapchainConfig {
+    pub vsync: bool,
+}
+
+impl Default for VSwapchainConfig {
+    fn default() -> Self {
+        Self { vsync: true }
+    }
+}
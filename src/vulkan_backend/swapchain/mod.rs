@@ -1,9 +1,12 @@
+pub mod config;
+
 use crate::shared::types::Id;
 use crate::vulkan_backend::device::VDevice;
 use crate::vulkan_backend::memory::VMemoryManager;
 use crate::vulkan_backend::memory::image::config::VImageConfig;
 use crate::vulkan_backend::memory::image::{VImage, image_view::VImageView};
 use crate::vulkan_backend::{device::VPhysicalDevice, instance::VInstance, surface::VSurface};
+use config::VSwapchainConfig;
 use ash::{khr, vk};
 use rand;
 use winit::window::Window;
@@ -28,6 +31,7 @@ impl VSwapchain {
         v_physical_device: &VPhysicalDevice,
         v_device: &VDevice,
         v_memory_manager: &VMemoryManager,
+        config: &VSwapchainConfig,
     ) -> Self {
         let swapchain_device = khr::swapchain::Device::new(&v_instance.instance, &v_device.device);
 
@@ -45,12 +49,15 @@ impl VSwapchain {
             .min_image_count(image_count)
             .image_extent(image_extent)
             .image_format(surface_format.format)
-            .present_mode(v_physical_device.select_present_mode())
+            .present_mode(v_physical_device.select_present_mode(config.vsync))
             .image_color_space(surface_format.color_space);
 
+        let present_queue_family_index = v_device
+            .present_queue_family_index
+            .expect("swapchain requires a device with a present queue");
         let graphics_and_present_queue_family_indices = [
             v_device.graphics_queue_family_index,
-            v_device.present_queue_family_index,
+            present_queue_family_index,
         ];
         if !v_device.is_graphics_and_present_queue_same {
             create_info = create_info
@@ -87,7 +94,7 @@ impl VSwapchain {
         } else {
             Some(vec![
                 v_device.graphics_queue_family_index,
-                v_device.present_queue_family_index,
+                present_queue_family_index,
             ])
         };
 
@@ -0,0 +1,188 @@
+// Orbit, follow and path controllers must place the active camera from their
+// target and input.
+
+mod common;
+
+use benzene::core::{
+    GameEngine,
+    ecs::{
+        components::{
+            CameraController, Transform3D,
+            camera_controller::{
+                CameraKeyframe, FollowController, OrbitController, PathController,
+            },
+        },
+        entities::game_object::GameObject,
+        types::{KeyboardInputEvent, MouseMotionEvent, MouseWheelEvent},
+    },
+    input::CursorMode,
+};
+use common::{set_empty_scene, spawn_camera, step};
+use nalgebra::{UnitQuaternion, Vector3};
+use winit::{
+    event::ElementState,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+// An active camera with the controller, and the target it is built around
+fn build_scene(
+    engine: &mut GameEngine,
+    controller: impl FnOnce(&GameObject) -> CameraController,
+) -> (GameObject, GameObject) {
+    set_empty_scene(engine);
+    let scene = engine.get_active_scene();
+    let target = scene.spawn("Target");
+    scene.add_transform_3d_component(
+        &target,
+        Transform3D::new(
+            Vector3::new(1.0, 2.0, -3.0),
+            Vector3::zeros(),
+            Vector3::new(1.0, 1.0, 1.0),
+        ),
+    );
+    let camera = spawn_camera(engine, Vector3::zeros());
+    let scene = engine.get_active_scene();
+    scene.add_camera_controller_component(&camera, controller(&target));
+    (camera, target)
+}
+
+fn get_camera_transform(engine: &mut GameEngine, camera: &GameObject) -> Transform3D {
+    engine
+        .get_active_scene()
+        .get::<Transform3D>(camera)
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn orbit_turns_and_zooms_around_the_target() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    let (camera, target) = build_scene(&mut engine, |target| {
+        CameraController::Orbit(OrbitController::new(*target.get_id(), 4.0))
+    });
+    let target_position = Vector3::new(1.0, 2.0, -3.0);
+
+    step(&mut engine);
+    let t = get_camera_transform(&mut engine, &camera);
+    assert!((t.get_world_position() - Vector3::new(1.0, 2.0, 1.0)).norm() < 1e-4);
+    assert!((t.get_forward() - -Vector3::z()).norm() < 1e-4);
+
+    // Mouse-look goes around the target at the same distance, facing it
+    engine.set_cursor_mode(CursorMode::Grabbed);
+    engine.handle_mouse_motion(&MouseMotionEvent::new(300.0, -150.0));
+    step(&mut engine);
+    let t = get_camera_transform(&mut engine, &camera);
+    let offset = t.get_world_position() - target_position;
+    assert!((offset.norm() - 4.0).abs() < 1e-4);
+    assert!((t.get_forward() + offset.normalize()).norm() < 1e-4);
+    assert!(offset.x.abs() > 0.1 && offset.y.abs() > 0.1);
+
+    // Scrolling up zooms in, down to the minimum distance
+    engine.handle_mouse_wheel(&MouseWheelEvent::new(0.0, 1.0));
+    step(&mut engine);
+    let t = get_camera_transform(&mut engine, &camera);
+    let distance = (t.get_world_position() - target_position).norm();
+    assert!((distance - 3.6).abs() < 1e-4);
+    engine.handle_mouse_wheel(&MouseWheelEvent::new(0.0, 100.0));
+    step(&mut engine);
+    let t = get_camera_transform(&mut engine, &camera);
+    let distance = (t.get_world_position() - target_position).norm();
+    assert!((distance - 0.5).abs() < 1e-4);
+
+    // The camera follows when the target moves
+    let scene = engine.get_active_scene();
+    scene.get_transform_3d_component(&target).position.y += 10.0;
+    step(&mut engine);
+    let t = get_camera_transform(&mut engine, &camera);
+    let distance = (t.get_world_position() - target_position - Vector3::y() * 10.0).norm();
+    assert!((distance - 0.5).abs() < 1e-4);
+
+    // Keys held while orbiting still count once the camera flies again
+    engine.handle_keyboard_input(&KeyboardInputEvent::new(
+        PhysicalKey::Code(KeyCode::KeyW),
+        ElementState::Pressed,
+        false,
+    ));
+    step(&mut engine);
+    let before = get_camera_transform(&mut engine, &camera);
+    let scene = engine.get_active_scene();
+    scene.add_camera_controller_component(&camera, CameraController::Fly);
+    step(&mut engine);
+    let t = get_camera_transform(&mut engine, &camera);
+    assert!((t.position - before.position).dot(&before.get_forward()) > 1e-3);
+    engine.destroy();
+}
+
+#[test]
+fn follow_settles_behind_the_target_without_overshooting() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    let offset = Vector3::new(0.0, 2.0, 6.0);
+    let (camera, target) = build_scene(&mut engine, |target| {
+        CameraController::Follow(FollowController::new(*target.get_id(), offset))
+    });
+
+    // Turned around, behind it is -Z
+    let scene = engine.get_active_scene();
+    scene.get_transform_3d_component(&target).rotation =
+        UnitQuaternion::from_euler_angles(0.0, 180f32.to_radians(), 0.0);
+    let goal = Vector3::new(1.0, 4.0, -9.0);
+
+    let mut previous_gap = f32::MAX;
+    for _ in 0..150 {
+        step(&mut engine);
+        let t = get_camera_transform(&mut engine, &camera);
+        let gap = (goal - t.position).norm();
+        assert!(gap <= previous_gap + 1e-4);
+        previous_gap = gap;
+    }
+    let t = get_camera_transform(&mut engine, &camera);
+    assert!((t.position - goal).norm() < 0.01);
+    let to_target = (Vector3::new(1.0, 2.0, -3.0) - t.position).normalize();
+    assert!((t.get_forward() - to_target).norm() < 1e-3);
+    engine.destroy();
+}
+
+#[test]
+fn path_passes_through_its_keyframes_and_stops_at_the_end() {
+    let keyframes = vec![
+        CameraKeyframe {
+            time: 0.0,
+            position: Vector3::new(0.0, 1.0, 5.0),
+            rotation: UnitQuaternion::identity(),
+        },
+        CameraKeyframe {
+            time: 2.0,
+            position: Vector3::new(10.0, 3.0, 0.0),
+            rotation: UnitQuaternion::from_euler_angles(0.0, 1.0, 0.0),
+        },
+        CameraKeyframe {
+            time: 1.0,
+            position: Vector3::new(5.0, 1.0, 2.0),
+            rotation: UnitQuaternion::from_euler_angles(0.0, 0.5, 0.0),
+        },
+    ];
+    let path = PathController::new(keyframes.clone());
+    assert_eq!(path.get_duration(), 2.0);
+    for keyframe in &keyframes {
+        let (position, rotation) = path.get_pose(keyframe.time);
+        assert!((position - keyframe.position).norm() < 1e-5);
+        assert!(rotation.angle_to(&keyframe.rotation) < 1e-5);
+    }
+
+    let mut engine = GameEngine::new_headless(64, 64);
+    let (camera, _) = build_scene(&mut engine, |_| {
+        CameraController::Path(PathController::new(keyframes.clone()))
+    });
+    // 16ms frames, a little past the end
+    for _ in 0..130 {
+        step(&mut engine);
+    }
+    let t = get_camera_transform(&mut engine, &camera);
+    assert!((t.position - Vector3::new(10.0, 3.0, 0.0)).norm() < 1e-5);
+    let scene = engine.get_active_scene();
+    match scene.get::<CameraController>(&camera).unwrap() {
+        CameraController::Path(path) => assert!(path.is_finished()),
+        _ => unreachable!(),
+    }
+    engine.destroy();
+}
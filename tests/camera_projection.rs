@@ -0,0 +1,166 @@
+// The active camera's projection must decide what is drawn, and its pose must
+// come from the entity's transform.
+
+mod common;
+
+use benzene::core::{
+    GameEngine,
+    ecs::components::{Camera3D, Projection, Transform3D},
+    scene_file::SceneData,
+};
+use common::{
+    golden::{GoldenConfig, compare_images, render_frames},
+    set_empty_scene, spawn_camera, spawn_model,
+};
+use image::RgbaImage;
+use nalgebra::{UnitQuaternion, Vector3};
+
+// A 10x10 wall facing a camera at the origin, `distance` away
+fn build_scene(engine: &mut GameEngine, projection: Projection, distance: f32, scale: f32) {
+    set_empty_scene(engine);
+    let camera = spawn_camera(engine, Vector3::zeros());
+    engine
+        .get_active_scene()
+        .get_mut::<Camera3D>(&camera)
+        .unwrap()
+        .projection = projection;
+    spawn_model(
+        engine,
+        "Wall",
+        "assets/models/plane.obj",
+        Transform3D::new(
+            Vector3::new(0.0, 0.0, -distance),
+            Vector3::new(90f32.to_radians(), 0.0, 0.0),
+            Vector3::new(scale, scale, scale),
+        ),
+    );
+}
+
+fn render(projection: Projection, distance: f32, scale: f32) -> RgbaImage {
+    render_frames(
+        &GoldenConfig::default(),
+        |engine| build_scene(engine, projection, distance, scale),
+        |_, _| {},
+    )
+}
+
+// The corners only ever show the clear color
+fn is_drawn_at(image: &RgbaImage, x: u32, y: u32) -> bool {
+    image.get_pixel(x, y) != image.get_pixel(0, 0)
+}
+
+#[test]
+fn far_plane_clips_distant_geometry() {
+    let config = GoldenConfig::default();
+    let (x, y) = (config.width / 2, config.height / 2);
+    let fov_y = 60f32.to_radians();
+
+    let default = render(Projection::default(), 150.0, 10.0);
+    assert!(!is_drawn_at(&default, x, y));
+
+    let far = render(
+        Projection::Perspective {
+            fov_y,
+            near: 0.1,
+            far: 1000.0,
+        },
+        150.0,
+        10.0,
+    );
+    assert!(is_drawn_at(&far, x, y));
+
+    let infinite = render(Projection::ReverseZ { fov_y, near: 0.1 }, 150.0, 10.0);
+    assert!(is_drawn_at(&infinite, x, y));
+}
+
+#[test]
+fn reverse_z_draws_like_perspective() {
+    let config = GoldenConfig::default();
+    let fov_y = 60f32.to_radians();
+    let perspective = render(
+        Projection::Perspective {
+            fov_y,
+            near: 0.1,
+            far: 100.0,
+        },
+        8.0,
+        0.5,
+    );
+    let reverse_z = render(Projection::ReverseZ { fov_y, near: 0.1 }, 8.0, 0.5);
+    assert!(is_drawn_at(&reverse_z, config.width / 2, config.height / 2));
+
+    let diff = compare_images(&perspective, &reverse_z, config.tolerance);
+    assert_eq!(diff.mismatched_pixels, 0);
+}
+
+#[test]
+fn orthographic_size_is_the_visible_height() {
+    let config = GoldenConfig::default();
+    let x = config.width / 2;
+    let projection = |size| Projection::Orthographic {
+        size,
+        near: 0.1,
+        far: 100.0,
+    };
+
+    // The wall fills the middle half of a view 20 units tall, at any distance
+    for distance in [5.0, 50.0] {
+        let image = render(projection(20.0), distance, 1.0);
+        assert!(is_drawn_at(&image, x, config.height / 2));
+        assert!(is_drawn_at(&image, x, config.height * 3 / 10));
+        assert!(!is_drawn_at(&image, x, config.height / 10));
+    }
+
+    let image = render(projection(8.0), 5.0, 1.0);
+    assert!(is_drawn_at(&image, x, config.height / 10));
+}
+
+#[test]
+fn camera_sees_from_its_entity_transform() {
+    let config = GoldenConfig::default();
+    let (x, y) = (config.width / 2, config.height / 2);
+    let moved = render_frames(
+        &config,
+        |engine| {
+            build_scene(engine, Projection::default(), 5.0, 1.0);
+            let scene = engine.get_active_scene();
+            let camera = scene.find_by_name("Camera").unwrap();
+            // Turned around, the wall is behind it
+            scene.get_transform_3d_component(&camera).rotation =
+                UnitQuaternion::from_euler_angles(0.0, 180f32.to_radians(), 0.0);
+        },
+        |_, _| {},
+    );
+    assert!(!is_drawn_at(&moved, x, y));
+}
+
+#[test]
+fn projection_is_saved_with_the_scene() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    build_scene(
+        &mut engine,
+        Projection::Orthographic {
+            size: 12.0,
+            near: 0.5,
+            far: 40.0,
+        },
+        5.0,
+        1.0,
+    );
+    let data = SceneData::from_scene(engine.get_active_scene());
+    let scene = engine.create_scene_from_data(&data);
+    engine.set_active_scene(scene);
+
+    let scene = engine.get_active_scene();
+    let camera = scene.find_by_name("Camera").unwrap();
+    assert!(scene.has::<Transform3D>(&camera));
+    assert!(matches!(
+        scene.get::<Camera3D>(&camera).unwrap().projection,
+        Projection::Orthographic {
+            size: 12.0,
+            near: 0.5,
+            far: 40.0
+        }
+    ));
+    engine.destroy();
+}
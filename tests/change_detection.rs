@@ -0,0 +1,128 @@
+// Systems must see each added, changed and removed component once.
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{
+    Arc,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use benzene::core::ecs::{
+    components::{PointLight3D, Transform3D},
+    entities::entity::Entity,
+    query::{Added, Changed},
+    schedule::{Stage, System},
+    systems::TRANSFORM_3D_SYSTEM,
+};
+use common::{new_headless_scene, step};
+use nalgebra::{Vector3, Vector4};
+
+struct Label(&'static str);
+
+#[test]
+fn systems_see_each_change_once() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let scene = engine.get_active_scene();
+    let mut entities = vec![];
+    for name in ["Crate", "Barrel", "Lamp"] {
+        let entity = scene.spawn(name);
+        scene.insert(&entity, Label(name));
+        scene.add_transform_3d_component(&entity, Transform3D::new_default());
+        entities.push(entity);
+    }
+    let lamp = entities[2].clone();
+    scene.add_point_light_3d_component(&lamp, PointLight3D::new(Vector4::new(1.0, 1.0, 1.0, 1.0)));
+
+    // Runs before the transform pass, whose rebuilt matrices must not show up
+    // as a second change
+    let early = Rc::new(RefCell::new(vec![]));
+    let seen_early = early.clone();
+    engine.add_system(System::new(
+        "watch_early",
+        Stage::Update,
+        move |scene, _| {
+            let mut names: Vec<&str> = scene
+                .query_filtered::<&Label, Changed<Transform3D>>()
+                .map(|label| label.0)
+                .collect();
+            names.sort();
+            seen_early.borrow_mut().push(names);
+        },
+    ));
+    let changed = Rc::new(RefCell::new(vec![]));
+    let removed = Rc::new(RefCell::new(vec![]));
+    let (seen_changed, seen_removed) = (changed.clone(), removed.clone());
+    engine.add_system(
+        System::new("watch", Stage::PostUpdate, move |scene, _| {
+            let mut names: Vec<&str> = scene
+                .query_filtered::<&Label, Changed<Transform3D>>()
+                .map(|label| label.0)
+                .collect();
+            names.sort();
+            seen_changed.borrow_mut().push(names);
+            let lights: Vec<Entity> = scene.get_removed::<PointLight3D>();
+            seen_removed.borrow_mut().push(lights);
+        })
+        .after(TRANSFORM_3D_SYSTEM),
+    );
+    let added = Arc::new(AtomicUsize::new(0));
+    let seen_added = added.clone();
+    engine.add_system(System::parallel_filtered::<&Label, Added<Label>>(
+        "count_added",
+        Stage::Update,
+        move |query, _| {
+            seen_added.fetch_add(query.count(), Ordering::Relaxed);
+        },
+    ));
+
+    step(&mut engine);
+    step(&mut engine);
+    let scene = engine.get_active_scene();
+    scene.get_transform_3d_component(&entities[0]).position.x += 1.0;
+    scene.remove_point_light_3d_component(&lamp);
+    step(&mut engine);
+
+    // Outside of systems, changes count over the last frame
+    let scene = engine.get_active_scene();
+    let outside: Vec<&str> = scene
+        .query_filtered::<&Label, Changed<Transform3D>>()
+        .map(|label| label.0)
+        .collect();
+    assert_eq!(outside, ["Crate"]);
+    step(&mut engine);
+
+    assert_eq!(
+        *changed.borrow(),
+        [
+            vec!["Barrel", "Crate", "Lamp"],
+            vec![],
+            vec!["Crate"],
+            vec![]
+        ]
+    );
+    assert_eq!(
+        *early.borrow(),
+        [
+            vec!["Barrel", "Crate", "Lamp"],
+            vec![],
+            vec!["Crate"],
+            vec![]
+        ]
+    );
+    assert_eq!(
+        *removed.borrow(),
+        [vec![], vec![], vec![*lamp.get_id()], vec![]]
+    );
+    assert_eq!(added.load(Ordering::Relaxed), 3);
+
+    let scene = engine.get_active_scene();
+    let crate_transform = scene.get::<Transform3D>(&entities[0]).unwrap();
+    assert_eq!(
+        crate_transform.get_world_position(),
+        Vector3::new(1.0, 0.0, 0.0)
+    );
+    engine.destroy();
+}
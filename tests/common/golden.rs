@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use benzene::core::GameEngine;
+use image::{Rgba, RgbaImage};
+
+// Set to rewrite every reference image with the current output
+pub const UPDATE_ENV: &str = "BENZENE_UPDATE_GOLDEN";
+
+pub struct GoldenConfig {
+    pub width: u32,
+    pub height: u32,
+    pub frames: usize,
+    pub dt: Duration,
+    // Max allowed difference per color channel before a pixel counts as changed
+    pub tolerance: u8,
+    // Number of changed pixels allowed before the comparison fails
+    pub max_mismatched_pixels: usize,
+}
+
+impl Default for GoldenConfig {
+    fn default() -> Self {
+        Self {
+            width: 320,
+            height: 240,
+            frames: 3,
+            dt: Duration::from_millis(16),
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+}
+
+pub struct GoldenDiff {
+    pub mismatched_pixels: usize,
+    pub max_channel_delta: u8,
+    pub diff_image: RgbaImage,
+}
+
+pub fn references_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+pub fn diffs_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden-diffs")
+}
+
+// Builds a headless engine, lets `setup` build the scene, then steps and
+// renders `config.frames` frames with a fixed dt. `update` runs before every
+// frame with the frame index.
+pub fn render_frames(
+    config: &GoldenConfig,
+    setup: impl FnOnce(&mut GameEngine),
+    mut update: impl FnMut(&mut GameEngine, usize),
+) -> RgbaImage {
+    let mut engine = GameEngine::new_headless(config.width, config.height);
+    setup(&mut engine);
+
+    for frame in 0..config.frames {
+        update(&mut engine, frame);
+        engine.pre_render_with_dt(config.dt);
+        engine.render_headless();
+    }
+
+    let image = engine
+        .read_frame()
+        .expect("failed to read back the rendered frame");
+    engine.destroy();
+    image
+}
+
+// Pixels whose channels all lie within the tolerance are drawn as a faded
+// copy of the reference, every other pixel is drawn solid red. Pixels outside
+// a smaller reference count as changed.
+pub fn compare_images(reference: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> GoldenDiff {
+    let mut diff_image = RgbaImage::new(actual.width(), actual.height());
+    let mut mismatched_pixels = 0;
+    let mut max_channel_delta = 0;
+
+    for (x, y, actual_pixel) in actual.enumerate_pixels() {
+        let Some(reference_pixel) = reference.get_pixel_checked(x, y) else {
+            mismatched_pixels += 1;
+            diff_image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            continue;
+        };
+        let delta = reference_pixel
+            .0
+            .iter()
+            .zip(actual_pixel.0.iter())
+            .map(|(r, a)| r.abs_diff(*a))
+            .max()
+            .unwrap_or(0);
+        max_channel_delta = max_channel_delta.max(delta);
+
+        if delta > tolerance {
+            mismatched_pixels += 1;
+            diff_image.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+        } else {
+            let [r, g, b, _] = reference_pixel.0;
+            let luma = ((r as u32 + g as u32 + b as u32) / 3 / 4) as u8;
+            diff_image.put_pixel(x, y, Rgba([luma, luma, luma, 255]));
+        }
+    }
+
+    GoldenDiff {
+        mismatched_pixels,
+        max_channel_delta,
+        diff_image,
+    }
+}
+
+// Compares against tests/golden/<name>.png, which must be committed. Only a
+// run with BENZENE_UPDATE_GOLDEN set records the image as the new reference.
+// On failure the actual and diff images go to target/golden-diffs.
+pub fn assert_golden(name: &str, actual: &RgbaImage, config: &GoldenConfig) {
+    let reference_path = references_dir().join(format!("{}.png", name));
+
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        std::fs::create_dir_all(references_dir()).expect("failed to create references dir");
+        actual
+            .save(&reference_path)
+            .expect("failed to save reference image");
+        println!("Recorded golden image {}", reference_path.display());
+        return;
+    }
+    assert!(
+        reference_path.exists(),
+        "golden image '{}' has no reference at {}, run with {}=1 to record it",
+        name,
+        reference_path.display(),
+        UPDATE_ENV
+    );
+
+    let reference = image::open(&reference_path)
+        .expect("failed to open reference image")
+        .to_rgba8();
+
+    let diff = compare_images(&reference, actual, config.tolerance);
+    let failure = if reference.dimensions() != actual.dimensions() {
+        Some(format!(
+            "size mismatch, reference: {:?}, actual: {:?}",
+            reference.dimensions(),
+            actual.dimensions()
+        ))
+    } else if diff.mismatched_pixels > config.max_mismatched_pixels {
+        Some(format!(
+            "{} pixels differ by more than {} (max delta: {}), allowed: {}",
+            diff.mismatched_pixels,
+            config.tolerance,
+            diff.max_channel_delta,
+            config.max_mismatched_pixels
+        ))
+    } else {
+        None
+    };
+
+    if let Some(reason) = failure {
+        std::fs::create_dir_all(diffs_dir()).expect("failed to create diffs dir");
+        let actual_path = diffs_dir().join(format!("{}.actual.png", name));
+        actual
+            .save(&actual_path)
+            .expect("failed to save actual image");
+        diff.diff_image
+            .save(diffs_dir().join(format!("{}.diff.png", name)))
+            .expect("failed to save diff image");
+        panic!(
+            "golden image '{}' does not match: {}. Actual image saved to {}",
+            name,
+            reason,
+            actual_path.display()
+        );
+    }
+}
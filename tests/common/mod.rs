@@ -0,0 +1,78 @@
+// Helpers shared by the integration tests. See Tests in the README for the
+// driver they need. Each test binary uses a different part of them.
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use benzene::core::{
+    GameEngine,
+    ecs::{
+        components::{Camera3D, Transform3D, directional_light_3d::DirectionalLight3D},
+        entities::game_object::GameObject,
+    },
+};
+use nalgebra::{Vector3, Vector4};
+
+pub mod golden;
+
+// Creates an empty scene and sets it as the active one
+pub fn set_empty_scene(engine: &mut GameEngine) {
+    let scene = engine.create_scene();
+    engine.set_active_scene(scene);
+}
+
+// A headless engine with an empty scene set as the active one
+pub fn new_headless_scene(width: u32, height: u32) -> GameEngine {
+    let mut engine = GameEngine::new_headless(width, height);
+    set_empty_scene(&mut engine);
+    engine
+}
+
+// Runs one frame's systems with a fixed dt, without rendering
+pub fn step(engine: &mut GameEngine) {
+    engine.pre_render_with_dt(Duration::from_millis(16));
+}
+
+// The active camera, at `position` and looking down -Z
+pub fn spawn_camera(engine: &mut GameEngine, position: Vector3<f32>) -> GameObject {
+    let scene = engine.get_active_scene();
+    let camera = scene.spawn("Camera");
+    scene.add_transform_3d_component(
+        &camera,
+        Transform3D::new(position, Vector3::zeros(), Vector3::new(1.0, 1.0, 1.0)),
+    );
+    scene.add_camera_3d_component(&camera, Camera3D::new_default());
+    scene.set_active_camera(&camera);
+    camera
+}
+
+// A directional light shining down at 45 degrees
+pub fn spawn_sun(engine: &mut GameEngine, color: Vector4<f32>) -> GameObject {
+    let scene = engine.get_active_scene();
+    let sun = scene.spawn("Sun");
+    scene.add_transform_3d_component(
+        &sun,
+        Transform3D::new(
+            Vector3::zeros(),
+            Vector3::new(0.0, 90f32.to_radians(), 45f32.to_radians()),
+            Vector3::new(1.0, 1.0, 1.0),
+        ),
+    );
+    scene.add_directional_light_3d_component(&sun, DirectionalLight3D::new(color));
+    sun
+}
+
+// An entity drawing the OBJ model at `path`
+pub fn spawn_model(
+    engine: &mut GameEngine,
+    name: &str,
+    path: &str,
+    transform: Transform3D,
+) -> GameObject {
+    let structure = engine.get_structure_3d_from_obj(path);
+    let scene = engine.get_active_scene();
+    let entity = scene.spawn(name);
+    scene.add_transform_3d_component(&entity, transform);
+    scene.add_structure_3d_component(&entity, structure);
+    entity
+}
@@ -0,0 +1,110 @@
+// Despawned handles must never reach the entity that reuses their slot.
+
+mod common;
+
+use benzene::core::ecs::components::{Tags, Transform3D};
+use common::new_headless_scene;
+
+struct Health(f32);
+
+#[test]
+fn stale_handles_are_detected() {
+    let mut engine = new_headless_scene(64, 64);
+    let scene = engine.get_active_scene();
+
+    let first = scene.spawn("First");
+    let second = scene.spawn("Second");
+    scene.insert(&first, Health(10.0));
+    scene.insert(&second, Health(20.0));
+    scene.despawn(&first);
+
+    // The freed slot is reused with a new generation
+    let third = scene.spawn("Third");
+    assert_eq!(third.get_id().get_index(), first.get_id().get_index());
+    assert_ne!(third.get_id(), first.get_id());
+    assert!(!scene.is_alive(&first));
+    assert!(scene.is_alive(&third));
+
+    scene.insert(&third, Health(30.0));
+    assert!(scene.get::<Health>(&first).is_none());
+    assert_eq!(scene.get::<Health>(&third).unwrap().0, 30.0);
+    assert_eq!(scene.get::<Health>(&second).unwrap().0, 20.0);
+    assert_eq!(scene.get_game_object(third.get_id()).unwrap().name, "Third");
+    engine.destroy();
+}
+
+#[test]
+#[should_panic(expected = "it was despawned")]
+fn inserting_into_a_despawned_entity_panics() {
+    let mut engine = new_headless_scene(64, 64);
+    let scene = engine.get_active_scene();
+
+    let entity = scene.spawn("Entity");
+    scene.despawn(&entity);
+    scene.spawn("Reused");
+    scene.add_transform_3d_component(&entity, Transform3D::new_default());
+}
+
+#[test]
+fn entities_are_handed_out_deterministically() {
+    let spawn_all = || {
+        let mut engine = new_headless_scene(64, 64);
+        let scene = engine.get_active_scene();
+
+        let entities: Vec<_> = (0..8)
+            .map(|index| scene.spawn(&format!("Entity {}", index)))
+            .collect();
+        scene.despawn(&entities[2]);
+        scene.despawn(&entities[5]);
+        let ids: Vec<_> = (0..3)
+            .map(|index| *scene.spawn(&format!("Late {}", index)).get_id())
+            .collect();
+        engine.destroy();
+        ids
+    };
+
+    assert_eq!(spawn_all(), spawn_all());
+}
+
+#[test]
+fn entities_are_found_by_name_and_tag() {
+    let mut engine = new_headless_scene(64, 64);
+    let scene = engine.get_active_scene();
+
+    let sun = scene.spawn("Sun");
+    let lamp = scene.spawn("Lamp");
+    let crate_entity = scene.spawn("Crate");
+    let second_lamp = scene.spawn("Lamp");
+    scene.add_tag(&sun, "light");
+    scene.add_tag(&second_lamp, "light");
+    scene.add_tag(&lamp, "light");
+    scene.add_tag(&crate_entity, "prop");
+
+    assert_eq!(scene.find_by_name("Sun").unwrap().get_id(), sun.get_id());
+    assert_eq!(scene.find_by_name("Lamp").unwrap().get_id(), lamp.get_id());
+    assert!(scene.find_by_name("Missing").is_none());
+
+    let lights: Vec<_> = scene
+        .find_all_with_tag("light")
+        .iter()
+        .map(|entity| *entity.get_id())
+        .collect();
+    assert_eq!(
+        lights,
+        [*sun.get_id(), *lamp.get_id(), *second_lamp.get_id()]
+    );
+
+    scene.remove_tag(&lamp, "light");
+    scene.despawn(&sun);
+    assert_eq!(scene.find_all_with_tag("light").len(), 1);
+    assert!(scene.get::<Tags>(&crate_entity).unwrap().has("prop"));
+
+    scene.rename(&second_lamp, "Street Lamp");
+    assert_eq!(
+        scene.find_by_name("Street Lamp").unwrap().get_id(),
+        second_lamp.get_id()
+    );
+    assert_eq!(scene.get_game_objects().count(), 3);
+    assert_eq!(format!("{:?}", second_lamp), "\"Lamp\" #3v0");
+    engine.destroy();
+}
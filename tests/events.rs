@@ -0,0 +1,93 @@
+// Events sent by one system must reach the others exactly once.
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use benzene::core::ecs::{
+    events::{EngineEvent, EntityDespawned, EventReader},
+    schedule::{Stage, System},
+};
+use common::{new_headless_scene, step};
+
+#[derive(Debug, PartialEq)]
+struct LightToggled(u32);
+
+#[test]
+fn systems_read_each_event_once() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let frame = Rc::new(RefCell::new(0));
+    let sender_frame = frame.clone();
+    engine.add_system(System::new("toggle", Stage::Update, move |scene, _| {
+        let mut frame = sender_frame.borrow_mut();
+        if *frame < 2 {
+            scene.send_event(LightToggled(*frame));
+        }
+        *frame += 1;
+    }));
+
+    // One reader runs after the sender, the other before it, so it only sees
+    // each event on the next frame
+    let late = Rc::new(RefCell::new(vec![]));
+    let early = Rc::new(RefCell::new(vec![]));
+    for (name, stage, seen) in [
+        ("late", Stage::PostUpdate, late.clone()),
+        ("early", Stage::PreUpdate, early.clone()),
+    ] {
+        let mut reader = EventReader::<LightToggled>::new();
+        engine.add_system(System::new(name, stage, move |scene, _| {
+            let frame_events: Vec<u32> = scene.read_events(&mut reader).map(|e| e.0).collect();
+            seen.borrow_mut().push(frame_events);
+        }));
+    }
+
+    for _ in 0..4 {
+        step(&mut engine);
+    }
+    assert_eq!(*late.borrow(), [vec![0], vec![1], vec![], vec![]]);
+    assert_eq!(*early.borrow(), [vec![], vec![0], vec![1], vec![]]);
+
+    // Unread events are dropped two frames after they were sent
+    let scene = engine.get_active_scene();
+    assert!(scene.get_events::<LightToggled>().unwrap().is_empty());
+    engine.destroy();
+}
+
+#[test]
+fn engine_events_are_published() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let scene = engine.get_active_scene();
+    let lamp = scene.spawn("Lamp");
+    scene.despawn(&lamp);
+    engine.resize_headless(32, 16);
+    step(&mut engine);
+
+    let scene = engine.get_active_scene();
+    let engine_events: Vec<_> = scene
+        .get_events::<EngineEvent>()
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect();
+    assert_eq!(
+        engine_events,
+        [
+            EngineEvent::SceneActivated,
+            EngineEvent::Resized {
+                width: 32,
+                height: 16
+            },
+        ]
+    );
+    let despawned: Vec<_> = scene
+        .get_events::<EntityDespawned>()
+        .unwrap()
+        .iter()
+        .map(|event| (event.entity, event.name.clone()))
+        .collect();
+    assert_eq!(despawned, [(*lamp.get_id(), "Lamp".to_string())]);
+    engine.destroy();
+}
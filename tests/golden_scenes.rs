@@ -0,0 +1,107 @@
+// Golden-image tests for the renderer.
+// Run with BENZENE_UPDATE_GOLDEN=1 to re-record the references after an
+// intended visual change.
+
+mod common;
+
+use benzene::core::ecs::{
+    components::{PointLight3D, Structure3D, Transform3D},
+    query::With,
+    systems::rotate_transform_3d,
+};
+use common::{
+    golden::{GoldenConfig, assert_golden, render_frames},
+    set_empty_scene, spawn_camera, spawn_model, spawn_sun,
+};
+use nalgebra::{Vector3, Vector4};
+
+#[test]
+fn directional_light_plane_and_vase() {
+    let config = GoldenConfig::default();
+    let image = render_frames(
+        &config,
+        |engine| {
+            set_empty_scene(engine);
+            spawn_camera(engine, Vector3::new(0.0, 1.0, 5.0));
+            spawn_sun(engine, Vector4::new(1.0, 0.95, 0.8, 0.6));
+
+            spawn_model(
+                engine,
+                "Plane",
+                "assets/models/plane.obj",
+                Transform3D::new(
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(2.0, 1.0, 2.0),
+                ),
+            );
+            spawn_model(
+                engine,
+                "Smooth Vase",
+                "assets/models/vase-smooth.obj",
+                Transform3D::new(
+                    Vector3::new(0.0, 0.0, -5.0),
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(1.0, 1.0, 1.0),
+                ),
+            );
+        },
+        |_, _| {},
+    );
+
+    assert_golden("directional_light_plane_and_vase", &image, &config);
+}
+
+#[test]
+fn point_light_spinning_torus() {
+    let config = GoldenConfig {
+        frames: 10,
+        ..GoldenConfig::default()
+    };
+    let image = render_frames(
+        &config,
+        |engine| {
+            set_empty_scene(engine);
+            spawn_camera(engine, Vector3::new(0.0, 1.0, 5.0));
+
+            let scene = engine.get_active_scene();
+            let light = scene.spawn("Red Light");
+            scene.add_transform_3d_component(
+                &light,
+                Transform3D::new(
+                    Vector3::new(2.0, 2.0, -2.0),
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(1.0, 1.0, 1.0),
+                ),
+            );
+            scene.add_point_light_3d_component(
+                &light,
+                PointLight3D::new(Vector4::new(1.0, 0.2, 0.2, 5.0)),
+            );
+
+            spawn_model(
+                engine,
+                "Torus",
+                "assets/models/torus-smooth.obj",
+                Transform3D::new(
+                    Vector3::new(0.0, 1.0, -3.0),
+                    Vector3::new(0.0, 0.0, 0.0),
+                    Vector3::new(1.0, 1.0, 1.0),
+                ),
+            );
+        },
+        |engine, _| {
+            // Rotation depends on the fixed dt, so every run lands on the same pose
+            let dt = config.dt.as_secs_f32();
+            // The torus is the only model in the scene
+            for transform in engine
+                .get_active_scene()
+                .query_filtered::<&mut Transform3D, With<Structure3D>>()
+            {
+                rotate_transform_3d(transform, Vector3::new(1.5 * dt, 0.0, 0.5 * dt));
+            }
+        },
+    );
+
+    assert_golden("point_light_spinning_torus", &image, &config);
+}
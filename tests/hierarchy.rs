@@ -0,0 +1,139 @@
+// World transforms must follow the parent chain.
+
+mod common;
+
+use benzene::core::{
+    GameEngine,
+    ecs::{
+        components::{PointLight3D, Transform3D},
+        entities::game_object::GameObject,
+        systems::translate_transform_3d,
+    },
+};
+use common::{new_headless_scene, step};
+use nalgebra::{Vector3, Vector4};
+
+fn spawn(engine: &mut GameEngine, name: &str, position: Vector3<f32>) -> GameObject {
+    let scene = engine.get_active_scene();
+    let entity = scene.spawn(name);
+    scene.add_transform_3d_component(
+        &entity,
+        Transform3D::new(
+            position,
+            Vector3::new(0.0, 90f32.to_radians(), 0.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ),
+    );
+    entity
+}
+
+fn world_position(engine: &mut GameEngine, entity: &GameObject) -> Vector3<f32> {
+    engine
+        .get_active_scene()
+        .get::<Transform3D>(entity)
+        .unwrap()
+        .get_world_position()
+}
+
+fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>) {
+    assert!(
+        (actual - expected).norm() < 1e-4,
+        "expected {:?}, got {:?}",
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn children_follow_their_parents() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let car = spawn(&mut engine, "Car", Vector3::new(10.0, 0.0, 0.0));
+    let lamp = spawn(&mut engine, "Lamp", Vector3::new(0.0, 1.0, -2.0));
+    let bulb = spawn(&mut engine, "Bulb", Vector3::new(0.0, 0.5, 0.0));
+    let scene = engine.get_active_scene();
+    scene.add_point_light_3d_component(&bulb, PointLight3D::new(Vector4::new(1.0, 1.0, 1.0, 1.0)));
+    scene.set_parent(&lamp, &car);
+    scene.set_parent(&bulb, &lamp);
+    step(&mut engine);
+
+    // Both parents are turned 90 degrees around Y, so local -Z is world -X
+    assert_close(
+        world_position(&mut engine, &lamp),
+        Vector3::new(8.0, 1.0, 0.0),
+    );
+    assert_close(
+        world_position(&mut engine, &bulb),
+        Vector3::new(8.0, 1.5, 0.0),
+    );
+
+    // Moving the root moves the whole subtree
+    translate_transform_3d(
+        engine.get_active_scene().get_transform_3d_component(&car),
+        Vector3::new(0.0, 0.0, 5.0),
+    );
+    step(&mut engine);
+    assert_close(
+        world_position(&mut engine, &bulb),
+        Vector3::new(8.0, 1.5, 5.0),
+    );
+
+    // Detached children keep their local transform, now relative to the world
+    engine.get_active_scene().remove_parent(&lamp);
+    step(&mut engine);
+    assert_close(
+        world_position(&mut engine, &lamp),
+        Vector3::new(0.0, 1.0, -2.0),
+    );
+    assert_close(
+        world_position(&mut engine, &bulb),
+        Vector3::new(0.0, 1.5, -2.0),
+    );
+
+    let scene = engine.get_active_scene();
+    assert!(scene.get_parent(&lamp).is_none());
+    assert!(scene.get_children(&car).is_empty());
+    assert_eq!(scene.get_children(&lamp), &[*bulb.get_id()]);
+
+    scene.despawn_recursive(&lamp);
+    assert!(!scene.has::<Transform3D>(&bulb));
+    engine.destroy();
+}
+
+#[test]
+fn despawning_a_parent_keeps_its_children_in_place() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let car = spawn(&mut engine, "Car", Vector3::new(10.0, 0.0, 0.0));
+    let lamp = spawn(&mut engine, "Lamp", Vector3::new(0.0, 1.0, -2.0));
+    let scene = engine.get_active_scene();
+    scene.set_parent(&lamp, &car);
+    step(&mut engine);
+    let before = world_position(&mut engine, &lamp);
+    assert_close(before, Vector3::new(8.0, 1.0, 0.0));
+
+    // Not recursive, the lamp is detached and stays where it was
+    engine.get_active_scene().despawn(&car);
+    step(&mut engine);
+    assert_close(world_position(&mut engine, &lamp), before);
+    let scene = engine.get_active_scene();
+    assert!(scene.is_alive(&lamp));
+    assert!(scene.get_parent(&lamp).is_none());
+    let lamp_transform = scene.get::<Transform3D>(&lamp).unwrap();
+    assert_close(lamp_transform.position, before);
+    // Both turns add up, local -Z now points to world +Z
+    assert_close(lamp_transform.get_forward(), Vector3::new(0.0, 0.0, 1.0));
+    engine.destroy();
+}
+
+#[test]
+#[should_panic(expected = "part of its own subtree")]
+fn parenting_to_a_descendant_panics() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let first = spawn(&mut engine, "First", Vector3::zeros());
+    let second = spawn(&mut engine, "Second", Vector3::zeros());
+    let scene = engine.get_active_scene();
+    scene.set_parent(&second, &first);
+    scene.set_parent(&first, &second);
+}
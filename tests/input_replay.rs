@@ -0,0 +1,103 @@
+// Replaying a recorded session must render the exact same frames.
+
+mod common;
+
+use std::path::PathBuf;
+
+use benzene::core::{
+    GameEngine,
+    ecs::{
+        components::Transform3D,
+        types::{KeyboardInputEvent, MouseMotionEvent},
+    },
+    input::{CursorMode, InputRecording},
+};
+use common::{set_empty_scene, spawn_camera, spawn_model, spawn_sun};
+use image::RgbaImage;
+use nalgebra::{Vector3, Vector4};
+use winit::{
+    event::ElementState,
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+const FRAMES: usize = 30;
+
+fn build_scene(engine: &mut GameEngine) {
+    set_empty_scene(engine);
+    spawn_camera(engine, Vector3::new(0.0, 1.0, 5.0));
+    spawn_sun(engine, Vector4::new(1.0, 1.0, 1.0, 0.8));
+    spawn_model(
+        engine,
+        "Vase",
+        "assets/models/vase-smooth.obj",
+        Transform3D::new_default(),
+    );
+}
+
+// Walks forward while looking around, the way a player would
+fn play(engine: &mut GameEngine, frame: usize) {
+    let key = |state| KeyboardInputEvent::new(PhysicalKey::Code(KeyCode::KeyW), state, false);
+    match frame {
+        2 => {
+            engine.set_cursor_mode(CursorMode::Grabbed);
+            engine.handle_keyboard_input(&key(ElementState::Pressed));
+        }
+        5..20 => engine.handle_mouse_motion(&MouseMotionEvent::new(12.0, -3.0)),
+        20 => engine.handle_keyboard_input(&key(ElementState::Released)),
+        _ => {}
+    }
+}
+
+// Steps with the measured frame time, so only the replay makes it repeatable
+fn run_frames(
+    engine: &mut GameEngine,
+    mut update: impl FnMut(&mut GameEngine, usize),
+) -> RgbaImage {
+    for frame in 0..FRAMES {
+        update(engine, frame);
+        engine.update_clock();
+        engine.pre_render();
+        engine.render_headless();
+        engine.end_frame();
+    }
+    engine
+        .read_frame()
+        .expect("failed to read back the rendered frame")
+}
+
+#[test]
+fn replay_reproduces_recorded_session() {
+    let mut engine = GameEngine::new_headless(320, 240);
+    build_scene(&mut engine);
+    engine.start_input_recording();
+    let recorded_frame = run_frames(&mut engine, play);
+    let recording = engine
+        .stop_input_recording()
+        .expect("recording was started");
+    engine.destroy();
+    assert_eq!(recording.get_frame_count(), FRAMES);
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/input_replay.ron");
+    recording.save_to_file(path.to_str().unwrap());
+    let recording = InputRecording::from_file(path.to_str().unwrap());
+
+    let mut engine = GameEngine::new_headless(320, 240);
+    build_scene(&mut engine);
+    engine.start_input_replay(recording);
+    // Live input is ignored while the replay runs
+    let replayed_frame = run_frames(&mut engine, |engine, frame| {
+        if frame == 10 {
+            engine.handle_keyboard_input(&KeyboardInputEvent::new(
+                PhysicalKey::Code(KeyCode::KeyS),
+                ElementState::Pressed,
+                false,
+            ));
+        }
+    });
+    engine.destroy();
+
+    assert!(
+        recorded_frame == replayed_frame,
+        "replayed frame differs from the recorded one"
+    );
+}
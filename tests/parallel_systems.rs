@@ -0,0 +1,123 @@
+// Parallel systems must produce the same scene as running them one by one.
+
+mod common;
+
+use benzene::core::{
+    GameEngine,
+    ecs::{
+        components::Transform3D,
+        entities::game_object::GameObject,
+        schedule::{Stage, System},
+        systems::rotate_transform_3d,
+    },
+};
+use common::{new_headless_scene, step};
+use nalgebra::{UnitQuaternion, Vector3};
+
+const ENTITIES: usize = 2000;
+const FRAMES: usize = 10;
+
+struct Velocity(Vector3<f32>);
+
+struct Spin(f32);
+
+fn build_scene(engine: &mut GameEngine) -> Vec<GameObject> {
+    let scene = engine.get_active_scene();
+
+    let mut entities = vec![];
+    for index in 0..ENTITIES {
+        let entity = scene.spawn(&format!("Entity {}", index));
+        scene.add_transform_3d_component(
+            &entity,
+            Transform3D::new(
+                Vector3::new(index as f32, 0.0, 0.0),
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0),
+            ),
+        );
+        scene.insert(&entity, Velocity(Vector3::new(0.0, index as f32, 1.0)));
+        if index % 2 == 0 {
+            scene.insert(&entity, Spin(index as f32 * 0.01));
+        }
+        entities.push(entity);
+    }
+    entities
+}
+
+fn add_systems(engine: &mut GameEngine, parallel: bool) {
+    if parallel {
+        engine.add_system(System::parallel::<(&mut Transform3D, &Velocity)>(
+            "move",
+            Stage::Update,
+            |query, ctx| {
+                for (t, velocity) in query {
+                    t.position += velocity.0 * ctx.dt;
+                }
+            },
+        ));
+        // "spin" conflicts with "move" and "accelerate" with "move", but
+        // "spin" and "accelerate" share a batch
+        engine.add_system(
+            System::parallel::<(&mut Transform3D, &Spin)>("spin", Stage::Update, |query, ctx| {
+                for (t, spin) in query {
+                    rotate_transform_3d(t, Vector3::new(0.0, spin.0 * ctx.dt, 0.0));
+                }
+            })
+            .after("move"),
+        );
+        engine.add_system(System::parallel::<&mut Velocity>(
+            "accelerate",
+            Stage::Update,
+            |query, ctx| {
+                for velocity in query {
+                    velocity.0.y -= 9.81 * ctx.dt;
+                }
+            },
+        ));
+    } else {
+        engine.add_system(System::new("move", Stage::Update, |scene, ctx| {
+            for (t, velocity) in scene.query::<(&mut Transform3D, &Velocity)>() {
+                t.position += velocity.0 * ctx.dt;
+            }
+        }));
+        engine.add_system(System::new("spin", Stage::Update, |scene, ctx| {
+            for (t, spin) in scene.query::<(&mut Transform3D, &Spin)>() {
+                rotate_transform_3d(t, Vector3::new(0.0, spin.0 * ctx.dt, 0.0));
+            }
+        }));
+        engine.add_system(System::new("accelerate", Stage::Update, |scene, ctx| {
+            for velocity in scene.query::<&mut Velocity>() {
+                velocity.0.y -= 9.81 * ctx.dt;
+            }
+        }));
+    }
+}
+
+fn run(parallel: bool) -> Vec<(Vector3<f32>, UnitQuaternion<f32>)> {
+    let mut engine = new_headless_scene(64, 64);
+    let entities = build_scene(&mut engine);
+    add_systems(&mut engine, parallel);
+    for _ in 0..FRAMES {
+        step(&mut engine);
+    }
+
+    let scene = engine.get_active_scene();
+    let transforms = entities
+        .iter()
+        .map(|entity| {
+            let t = scene.get::<Transform3D>(entity).unwrap();
+            assert!(
+                t.get_world_position() == t.position,
+                "moved transforms were not rebuilt"
+            );
+            (t.position, t.rotation)
+        })
+        .collect();
+    engine.destroy();
+    transforms
+}
+
+#[test]
+fn parallel_systems_match_sequential_ones() {
+    assert!(run(true) == run(false));
+}
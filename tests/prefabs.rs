@@ -0,0 +1,115 @@
+// Prefab instances must share their GPU resources and take per-instance
+// overrides.
+
+mod common;
+
+use std::sync::Arc;
+
+use benzene::core::{
+    GameEngine,
+    ecs::components::{Material3D, Structure3D, Transform3D, spot_light_3d::SpotLight3D},
+    prefab::PrefabOverrides,
+};
+use common::{new_headless_scene, step};
+use nalgebra::{Vector3, Vector4};
+
+const LAMP_POST: &str = "assets/prefabs/lamp_post.ron";
+
+#[test]
+fn instances_share_models_and_take_overrides() {
+    let mut engine = new_headless_scene(64, 64);
+
+    let textures = engine.get_texture_count();
+    let prefab = engine.load_prefab(LAMP_POST);
+    assert_eq!(prefab.get_entity_count(), 2);
+    let first = engine.instantiate_prefab(&prefab, PrefabOverrides::default());
+    let second = engine.instantiate_prefab(
+        &prefab,
+        PrefabOverrides {
+            name: Some("Red Lamp Post".into()),
+            transform: Some(Transform3D::new(
+                Vector3::new(4.0, 0.0, 0.0),
+                Vector3::zeros(),
+                Vector3::new(0.5, 1.5, 0.5),
+            )),
+            light_color: Some(Vector4::new(1.0, 0.0, 0.0, 4.0)),
+        },
+    );
+    engine.unload_prefab(prefab);
+    // Still used by both instances
+    assert_eq!(engine.get_model_count(), 1);
+    assert_eq!(engine.get_texture_count(), textures + 1);
+    step(&mut engine);
+
+    let scene = engine.get_active_scene();
+    assert_eq!(first.name, "Lamp Post");
+    assert_eq!(second.name, "Red Lamp Post");
+    assert!(Arc::ptr_eq(
+        &scene.get::<Structure3D>(&first).unwrap().model,
+        &scene.get::<Structure3D>(&second).unwrap().model,
+    ));
+    assert_eq!(
+        scene.get::<Material3D>(&first).unwrap().manager_index,
+        scene.get::<Material3D>(&second).unwrap().manager_index,
+    );
+
+    // The lamp is the only child of each instance and follows its root
+    let first_lamp = *scene.get_children(&first).first().unwrap();
+    let second_lamp = *scene.get_children(&second).first().unwrap();
+    let lamp_color = |entity| {
+        let object = scene.get_game_object(&entity).unwrap().clone();
+        scene.get::<SpotLight3D>(&object).unwrap().color
+    };
+    assert_eq!(lamp_color(first_lamp), Vector4::new(1.0, 0.9, 0.7, 4.0));
+    assert_eq!(lamp_color(second_lamp), Vector4::new(1.0, 0.0, 0.0, 4.0));
+
+    let second_lamp = scene.get_game_object(&second_lamp).unwrap().clone();
+    let position = scene
+        .get::<Transform3D>(&second_lamp)
+        .unwrap()
+        .get_world_position();
+    assert!((position - Vector3::new(4.0, 3.0, 0.0)).norm() < 1e-4);
+
+    // Despawning one instance leaves the shared model to the other
+    let model = scene.get::<Structure3D>(&second).unwrap().model.clone();
+    let users = Arc::strong_count(&model);
+    scene.despawn_recursive(&first);
+    assert_eq!(Arc::strong_count(&model), users - 1);
+    // Still loaded, and drawn for the other instance
+    step(&mut engine);
+    engine.render_headless();
+    let scene = engine.get_active_scene();
+    assert!(Arc::ptr_eq(
+        &scene.get::<Structure3D>(&second).unwrap().model,
+        &model
+    ));
+    assert_eq!(engine.get_model_count(), 1);
+
+    // The last instance takes the model, texture and material with it
+    drop(model);
+    engine.get_active_scene().despawn_recursive(&second);
+    step(&mut engine);
+    engine.render_headless();
+    assert_eq!(engine.get_model_count(), 0);
+    assert_eq!(engine.get_texture_count(), textures);
+    engine.destroy();
+}
+
+#[test]
+fn unloading_an_unused_prefab_frees_its_assets() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    let textures = engine.get_texture_count();
+    let prefab = engine.load_prefab(LAMP_POST);
+    assert_eq!(engine.get_model_count(), 1);
+    assert_eq!(engine.get_texture_count(), textures + 1);
+
+    engine.unload_prefab(prefab);
+    assert_eq!(engine.get_model_count(), 0);
+    assert_eq!(engine.get_texture_count(), textures);
+
+    // Loading it again after that still works
+    let prefab = engine.load_prefab(LAMP_POST);
+    assert_eq!(engine.get_texture_count(), textures + 1);
+    engine.unload_prefab(prefab);
+    engine.destroy();
+}
@@ -0,0 +1,218 @@
+// Scenes saved to RON must load back into the same scene.
+
+mod common;
+
+use std::path::PathBuf;
+
+use benzene::core::{
+    GameEngine,
+    ecs::components::{Material3D, Structure3D, Transform3D},
+    scene_file::{CameraData, EntityData, ProjectionData, SceneData, SpotLightData},
+};
+use common::golden::{GoldenConfig, compare_images, render_frames};
+use nalgebra::UnitQuaternion;
+
+const EXAMPLE_SCENE: &str = "assets/scenes/example.ron";
+
+fn out_path(name: &str) -> String {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/scene-file-tests");
+    std::fs::create_dir_all(&dir).expect("failed to create the output directory");
+    dir.join(name).to_string_lossy().into_owned()
+}
+
+fn load_and_activate(engine: &mut GameEngine, path: &str) {
+    let scene = engine.load_scene(path);
+    engine.set_active_scene(scene);
+}
+
+#[test]
+fn loaded_scene_resolves_assets_and_hierarchy() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    load_and_activate(&mut engine, EXAMPLE_SCENE);
+    let scene = engine.get_active_scene();
+
+    let objects: Vec<_> = scene.get_game_objects().cloned().collect();
+    let by_name = |name: &str| objects.iter().find(|o| o.name == name).unwrap();
+    let ground = by_name("Ground");
+    let vase = by_name("Vase");
+    let lamp = by_name("Lamp");
+
+    assert_eq!(objects.len(), 6);
+    assert_eq!(scene.get_parent(vase), Some(ground.get_id()));
+    assert_eq!(scene.get_parent(lamp), Some(vase.get_id()));
+    assert_eq!(
+        scene
+            .get::<Structure3D>(vase)
+            .unwrap()
+            .asset_path
+            .as_deref(),
+        Some("assets/models/vase-smooth.obj")
+    );
+    assert_eq!(
+        scene
+            .get::<Material3D>(ground)
+            .unwrap()
+            .texture_path
+            .as_deref(),
+        Some("assets/textures/cracked-dirt512x512.jpg")
+    );
+    assert!(scene.has::<Transform3D>(lamp));
+    assert_eq!(scene.get_active_camera(), Some(by_name("Camera").get_id()));
+    engine.destroy();
+}
+
+fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+    let delta = actual
+        .iter()
+        .zip(expected.iter())
+        .fold(0f32, |max, (a, e)| max.max((a - e).abs()));
+    assert!(delta < 1e-5, "expected {:?}, got {:?}", expected, actual);
+}
+
+// Angles pointing straight up or down have more than one Euler form, so
+// rotations are compared as orientations
+fn get_rotation(angles: [f32; 3]) -> UnitQuaternion<f32> {
+    UnitQuaternion::from_euler_angles(angles[0], angles[1], angles[2])
+}
+
+// Every saved field
+fn assert_same_entity(a: &EntityData, b: &EntityData) {
+    assert_eq!(a.name, b.name);
+    assert_eq!(a.parent, b.parent, "parent of {}", a.name);
+    assert_eq!(a.mesh, b.mesh, "mesh of {}", a.name);
+    assert_eq!(a.texture, b.texture, "texture of {}", a.name);
+    assert_eq!(a.tags, b.tags, "tags of {}", a.name);
+    assert_eq!(a.camera, b.camera, "camera of {}", a.name);
+    assert_eq!(a.point_light, b.point_light, "point light of {}", a.name);
+    assert_eq!(
+        a.directional_light, b.directional_light,
+        "directional light of {}",
+        a.name
+    );
+    assert_eq!(a.spot_light, b.spot_light, "spot light of {}", a.name);
+    assert_eq!(a.transform.is_some(), b.transform.is_some());
+    if let (Some(a), Some(b)) = (&a.transform, &b.transform) {
+        assert_close(a.position, b.position);
+        assert_close(a.scale, b.scale);
+        assert!(
+            get_rotation(a.rotation).angle_to(&get_rotation(b.rotation)) < 1e-3,
+            "expected rotation {:?}, got {:?}",
+            a.rotation,
+            b.rotation
+        );
+    }
+}
+
+fn assert_same_scene(a: &SceneData, b: &SceneData) {
+    assert_eq!(a.ambient_color, b.ambient_color);
+    assert_eq!(a.active_camera, b.active_camera);
+    assert_eq!(a.entities.len(), b.entities.len());
+    for (a, b) in a.entities.iter().zip(b.entities.iter()) {
+        assert_same_entity(a, b);
+    }
+}
+
+fn get_ground_material(engine: &mut GameEngine, scene_name: &str) -> usize {
+    let scene = engine.get_scene(scene_name).unwrap();
+    let ground = scene.find_by_name("Ground").unwrap();
+    scene.get::<Material3D>(&ground).unwrap().manager_index
+}
+
+#[test]
+fn scenes_loaded_from_files_share_textures() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    let textures = engine.get_texture_count();
+    let first = engine.load_scene(EXAMPLE_SCENE);
+    engine.add_scene("first", first);
+    assert_eq!(engine.get_texture_count(), textures + 2);
+
+    let second = engine.load_scene(EXAMPLE_SCENE);
+    engine.add_scene("second", second);
+    assert_eq!(engine.get_texture_count(), textures + 2);
+    assert_eq!(
+        get_ground_material(&mut engine, "first"),
+        get_ground_material(&mut engine, "second")
+    );
+    engine.destroy();
+}
+
+#[test]
+fn save_load_round_trip() {
+    let first_path = out_path("first.ron");
+    let second_path = out_path("second.ron");
+
+    let mut engine = GameEngine::new_headless(64, 64);
+    load_and_activate(&mut engine, EXAMPLE_SCENE);
+    engine.get_active_scene().save_to_file(&first_path);
+    load_and_activate(&mut engine, &first_path);
+    engine.get_active_scene().save_to_file(&second_path);
+    engine.destroy();
+
+    let example = SceneData::from_file(EXAMPLE_SCENE);
+    let first = SceneData::from_file(&first_path);
+    let second = SceneData::from_file(&second_path);
+    assert_same_scene(&example, &first);
+    assert_same_scene(&first, &second);
+
+    // The fields the file leaves out come back with their defaults
+    let by_name = |name: &str| first.entities.iter().find(|e| e.name == name).unwrap();
+    assert_eq!(first.entities.len(), 6);
+    assert_eq!(first.active_camera, Some(0));
+    assert_eq!(
+        by_name("Camera").camera,
+        Some(CameraData {
+            projection: ProjectionData::Perspective {
+                fov_y: std::f32::consts::FRAC_PI_3,
+                near: 0.1,
+                far: 100.0,
+            },
+            speed: 5.0,
+            sensitivity: 0.002,
+            invert_y: false,
+        })
+    );
+    assert_eq!(
+        by_name("Spot").spot_light,
+        Some(SpotLightData {
+            color: [0.8, 0.9, 1.0, 4.0],
+            shadows: true,
+        })
+    );
+    assert_eq!(
+        by_name("Lamp")
+            .point_light
+            .as_ref()
+            .map(|light| light.color),
+        Some([1.0, 0.6, 0.3, 3.0])
+    );
+    assert_eq!(
+        by_name("Sun")
+            .directional_light
+            .as_ref()
+            .map(|light| light.color),
+        Some([1.0, 0.95, 0.8, 0.6])
+    );
+}
+
+#[test]
+fn loaded_scene_renders_like_the_saved_one() {
+    let saved_path = out_path("rendered.ron");
+    let config = GoldenConfig::default();
+
+    let original = render_frames(
+        &config,
+        |engine| {
+            load_and_activate(engine, EXAMPLE_SCENE);
+            engine.get_active_scene().save_to_file(&saved_path);
+        },
+        |_, _| {},
+    );
+    let reloaded = render_frames(
+        &config,
+        |engine| load_and_activate(engine, &saved_path),
+        |_, _| {},
+    );
+
+    let diff = compare_images(&original, &reloaded, config.tolerance);
+    assert_eq!(diff.mismatched_pixels, 0);
+}
@@ -0,0 +1,104 @@
+// Despawned entities must leave no trace in later frames.
+
+mod common;
+
+use benzene::{
+    constants::MAX_FRAMES_IN_FLIGHT,
+    core::{
+        GameEngine,
+        ecs::{
+            components::{PointLight3D, Transform3D},
+            entities::game_object::GameObject,
+        },
+    },
+};
+use common::{
+    golden::{GoldenConfig, compare_images, render_frames},
+    set_empty_scene, spawn_camera, spawn_model, spawn_sun,
+};
+use nalgebra::{Vector3, Vector4};
+
+fn build_scene(engine: &mut GameEngine) {
+    set_empty_scene(engine);
+    spawn_camera(engine, Vector3::new(0.0, 1.0, 5.0));
+    spawn_sun(engine, Vector4::new(1.0, 1.0, 1.0, 0.6));
+    spawn_model(
+        engine,
+        "Plane",
+        "assets/models/plane.obj",
+        Transform3D::new(
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 1.0, 2.0),
+        ),
+    );
+}
+
+// A vase lit by a red point light, both removed again later
+fn spawn_extras(engine: &mut GameEngine) -> (GameObject, GameObject) {
+    let vase = spawn_model(
+        engine,
+        "Vase",
+        "assets/models/vase-smooth.obj",
+        Transform3D::new_default(),
+    );
+
+    let scene = engine.get_active_scene();
+    let lamp = scene.spawn("Lamp");
+    scene.add_transform_3d_component(
+        &lamp,
+        Transform3D::new(
+            Vector3::new(0.0, 1.0, 1.0),
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ),
+    );
+    scene.add_point_light_3d_component(&lamp, PointLight3D::new(Vector4::new(1.0, 0.0, 0.0, 5.0)));
+
+    (vase, lamp)
+}
+
+#[test]
+fn despawned_entities_are_not_rendered() {
+    // Enough frames after the removal for the deletion queue to release the
+    // vase buffers
+    let removed_at = 2;
+    let config = GoldenConfig {
+        frames: removed_at + MAX_FRAMES_IN_FLIGHT + 1,
+        ..GoldenConfig::default()
+    };
+    let expected = render_frames(&config, build_scene, |_, _| {});
+
+    let with_extras = render_frames(
+        &GoldenConfig {
+            frames: removed_at,
+            ..GoldenConfig::default()
+        },
+        build_scene,
+        |engine, frame| {
+            if frame == 0 {
+                spawn_extras(engine);
+            }
+        },
+    );
+    let diff = compare_images(&expected, &with_extras, config.tolerance);
+    assert!(diff.mismatched_pixels > config.max_mismatched_pixels);
+
+    let mut extras = None;
+    let actual = render_frames(&config, build_scene, |engine, frame| {
+        if frame == 0 {
+            extras = Some(spawn_extras(engine));
+        } else if frame == removed_at {
+            let (vase, lamp) = extras.take().unwrap();
+            let scene = engine.get_active_scene();
+            scene.despawn(&vase);
+            scene.remove_point_light_3d_component(&lamp);
+        }
+    });
+    let diff = compare_images(&expected, &actual, config.tolerance);
+    assert!(
+        diff.mismatched_pixels <= config.max_mismatched_pixels,
+        "despawned entities are still rendered, {} pixels differ",
+        diff.mismatched_pixels
+    );
+}
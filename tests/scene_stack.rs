@@ -0,0 +1,218 @@
+// Named scenes, overlays and preloading must switch what runs and what is
+// drawn without leaking scenes.
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use benzene::core::{
+    GameEngine,
+    ecs::{
+        events::{EngineEvent, EventReader},
+        schedule::{Stage, System},
+    },
+    engine::DEFAULT_SCENE,
+    scene_stack::OverlayConfig,
+};
+use common::{
+    golden::{GoldenConfig, compare_images, render_frames},
+    new_headless_scene, step,
+};
+
+const EXAMPLE_SCENE: &str = "assets/scenes/example.ron";
+
+// Counts the frames the scene's systems ran in
+fn add_counted_scene(engine: &mut GameEngine, name: &str) -> Rc<RefCell<usize>> {
+    let mut scene = engine.create_scene();
+    let runs = Rc::new(RefCell::new(0));
+    let counter = runs.clone();
+    scene.add_system(System::new("count", Stage::Update, move |_, _| {
+        *counter.borrow_mut() += 1;
+    }));
+    engine.add_scene(name, scene);
+    runs
+}
+
+#[test]
+fn overlays_pause_the_scenes_below() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    let game = add_counted_scene(&mut engine, "game");
+    let hud = add_counted_scene(&mut engine, "hud");
+    let menu = add_counted_scene(&mut engine, "menu");
+
+    engine.switch_to_scene("game");
+    engine.push_overlay("hud", OverlayConfig::default());
+    step(&mut engine);
+    assert_eq!((*game.borrow(), *hud.borrow(), *menu.borrow()), (1, 1, 0));
+
+    engine.push_overlay(
+        "menu",
+        OverlayConfig {
+            pause_below: true,
+            capture_input: true,
+        },
+    );
+    assert_eq!(engine.get_scene_stack(), ["game", "hud", "menu"]);
+    step(&mut engine);
+    assert_eq!((*game.borrow(), *hud.borrow(), *menu.borrow()), (1, 1, 1));
+
+    assert_eq!(engine.pop_overlay().as_deref(), Some("menu"));
+    step(&mut engine);
+    assert_eq!((*game.borrow(), *hud.borrow(), *menu.borrow()), (2, 2, 1));
+
+    // Switching drops the overlays, every scene stays loaded until unloaded
+    engine.switch_to_scene("menu");
+    assert_eq!(engine.get_scene_stack(), ["menu"]);
+    assert!(engine.has_scene("game") && engine.has_scene("hud"));
+    assert!(engine.unload_scene("game"));
+    assert!(!engine.has_scene("game"));
+    assert!(!engine.unload_scene("game"));
+    assert_eq!(engine.pop_overlay(), None);
+    engine.destroy();
+}
+
+#[test]
+fn set_active_scene_unloads_the_previous_one() {
+    let mut engine = new_headless_scene(64, 64);
+    engine.get_active_scene().spawn("First");
+
+    let scene = engine.create_scene();
+    engine.set_active_scene(scene);
+    let scene = engine.get_active_scene();
+    assert_eq!(scene.get_game_objects().count(), 0);
+    assert_eq!(engine.get_active_scene_name(), Some(DEFAULT_SCENE));
+
+    // Activated scenes are told on their own bus
+    let mut reader = EventReader::<EngineEvent>::new();
+    let events: Vec<_> = engine
+        .get_active_scene()
+        .read_events(&mut reader)
+        .cloned()
+        .collect();
+    assert_eq!(events, [EngineEvent::SceneActivated]);
+    engine.destroy();
+}
+
+#[test]
+fn unloading_scenes_frees_their_assets() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    let textures = engine.get_texture_count();
+    let scene = engine.load_scene(EXAMPLE_SCENE);
+    engine.add_scene("first", scene);
+    let scene = engine.load_scene(EXAMPLE_SCENE);
+    engine.add_scene("second", scene);
+    let models = engine.get_model_count();
+    assert_eq!(models, 2);
+    assert_eq!(engine.get_texture_count(), textures + 2);
+
+    // Still used by the other scene
+    engine.unload_scene("first");
+    assert_eq!(engine.get_model_count(), models);
+    assert_eq!(engine.get_texture_count(), textures + 2);
+
+    engine.unload_scene("second");
+    assert_eq!(engine.get_model_count(), 0);
+    assert_eq!(engine.get_texture_count(), textures);
+
+    // Replacing a scene under its name unloads the old one as well
+    for _ in 0..3 {
+        let scene = engine.load_scene(EXAMPLE_SCENE);
+        engine.set_active_scene(scene);
+    }
+    assert_eq!(engine.get_model_count(), models);
+    assert_eq!(engine.get_texture_count(), textures + 2);
+    engine.destroy();
+}
+
+#[test]
+fn preloaded_scene_matches_a_loaded_one() {
+    let mut engine = GameEngine::new_headless(64, 64);
+    engine.preload_scene("example", EXAMPLE_SCENE);
+    assert!(engine.is_scene_preloading("example"));
+
+    // Waits for the file if the thread isn't done yet
+    engine.switch_to_scene("example");
+    assert!(!engine.is_scene_preloading("example"));
+    let mut preloaded: Vec<String> = engine
+        .get_active_scene()
+        .get_game_objects()
+        .map(|object| object.name.clone())
+        .collect();
+    preloaded.sort();
+
+    let scene = engine.load_scene(EXAMPLE_SCENE);
+    engine.add_scene("loaded", scene);
+    let mut loaded: Vec<String> = engine
+        .get_scene("loaded")
+        .unwrap()
+        .get_game_objects()
+        .map(|object| object.name.clone())
+        .collect();
+    loaded.sort();
+    assert_eq!(preloaded, loaded);
+    engine.destroy();
+}
+
+#[test]
+fn overlay_is_drawn_over_the_active_scene() {
+    let config = GoldenConfig::default();
+    let alone = render_frames(
+        &config,
+        |engine| {
+            let scene = engine.load_scene(EXAMPLE_SCENE);
+            engine.set_active_scene(scene);
+        },
+        |_, _| {},
+    );
+    // The empty scene below only contributes the clear color
+    let overlaid = render_frames(
+        &config,
+        |engine| {
+            let scene = engine.create_scene();
+            engine.set_active_scene(scene);
+            let overlay = engine.load_scene(EXAMPLE_SCENE);
+            engine.add_scene("overlay", overlay);
+            engine.push_overlay("overlay", OverlayConfig::default());
+        },
+        |_, _| {},
+    );
+
+    let diff = compare_images(&alone, &overlaid, config.tolerance);
+    assert_eq!(diff.mismatched_pixels, 0);
+}
+
+#[test]
+fn scene_paused_from_the_start_is_still_drawn() {
+    let config = GoldenConfig::default();
+    let alone = render_frames(
+        &config,
+        |engine| {
+            let scene = engine.load_scene(EXAMPLE_SCENE);
+            engine.set_active_scene(scene);
+        },
+        |_, _| {},
+    );
+    // Paused before its first frame, its camera and lights are uploaded all
+    // the same; the empty menu over it only clears depth
+    let paused = render_frames(
+        &config,
+        |engine| {
+            let scene = engine.load_scene(EXAMPLE_SCENE);
+            engine.set_active_scene(scene);
+            let menu = engine.create_scene();
+            engine.add_scene("menu", menu);
+            engine.push_overlay(
+                "menu",
+                OverlayConfig {
+                    pause_below: true,
+                    capture_input: true,
+                },
+            );
+        },
+        |_, _| {},
+    );
+
+    let diff = compare_images(&alone, &paused, config.tolerance);
+    assert_eq!(diff.mismatched_pixels, 0);
+}